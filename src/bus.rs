@@ -0,0 +1,152 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gst::{bus::BusWatchGuard, prelude::*, MessageView, Pipeline};
+
+use crate::{error::GstreamerError, recording::RECORDING_FINISHED, tracks::TRACKS_CHANGED};
+
+pub const EOS_ACTION_NAMES: &[&str] = &["Stop at end", "Rewind at end", "Loop"];
+
+#[derive(Debug, Clone)]
+pub enum PlayerEvent {
+    Eos,
+    Error {
+        source: String,
+        message: String,
+        debug: Option<String>,
+    },
+    Warning {
+        source: String,
+        message: String,
+        debug: Option<String>,
+    },
+    Buffering(i32),
+    StateChanged {
+        old: gst::State,
+        current: gst::State,
+    },
+    DurationChanged,
+    StreamStart,
+    // End of a segment seek, used for A-B looping
+    SegmentDone,
+    RecordingFinished(String),
+    Tags(gst::TagList),
+    // Streams playbin3 found and the ones it plays
+    StreamCollection(gst::StreamCollection),
+    StreamsSelected(Vec<gst::Stream>),
+    // An audio or subtitle track showed up or went away (decodebin)
+    TracksChanged,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EosAction {
+    #[default]
+    Stop,
+    Rewind,
+    Loop,
+}
+
+impl EosAction {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => EosAction::Rewind,
+            2 => EosAction::Loop,
+            _ => EosAction::Stop,
+        }
+    }
+}
+
+type Subscriber = Rc<dyn Fn(&PlayerEvent)>;
+
+#[derive(Clone)]
+pub struct BusWatcher {
+    subscribers: Rc<RefCell<Vec<Subscriber>>>,
+    _watch_guard: Rc<BusWatchGuard>,
+}
+
+impl BusWatcher {
+    pub fn new(pipeline: &Pipeline) -> Result<Self, GstreamerError> {
+        let bus = pipeline
+            .bus()
+            .ok_or_else(|| glib::bool_error!("Pipeline without bus"))?;
+        let subscribers: Rc<RefCell<Vec<Subscriber>>> = Rc::new(RefCell::new(Vec::new()));
+
+        let subscribers_clone = subscribers.clone();
+        let pipeline_weak = pipeline.downgrade();
+        let watch_guard = bus.add_watch_local(move |_bus, msg| {
+            let Some(pipeline) = pipeline_weak.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            if let Some(event) = event_from_message(msg, &pipeline) {
+                // Clone the list so subscribers can subscribe from inside a callback
+                let subscribers = subscribers_clone.borrow().clone();
+                for subscriber in subscribers {
+                    subscriber(&event);
+                }
+            }
+            glib::ControlFlow::Continue
+        })?;
+
+        Ok(Self {
+            subscribers,
+            _watch_guard: Rc::new(watch_guard),
+        })
+    }
+
+    pub fn subscribe(&self, subscriber: impl Fn(&PlayerEvent) + 'static) {
+        self.subscribers.borrow_mut().push(Rc::new(subscriber));
+    }
+}
+
+fn event_from_message(msg: &gst::Message, pipeline: &Pipeline) -> Option<PlayerEvent> {
+    let source = || {
+        msg.src()
+            .map(|src| src.path_string().to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+
+    match msg.view() {
+        MessageView::Eos(_) => Some(PlayerEvent::Eos),
+        MessageView::Error(err) => Some(PlayerEvent::Error {
+            source: source(),
+            message: err.error().to_string(),
+            debug: err.debug().map(|debug| debug.to_string()),
+        }),
+        MessageView::Warning(warning) => Some(PlayerEvent::Warning {
+            source: source(),
+            message: warning.error().to_string(),
+            debug: warning.debug().map(|debug| debug.to_string()),
+        }),
+        MessageView::Buffering(buffering) => Some(PlayerEvent::Buffering(buffering.percent())),
+        // Only the pipeline's own transitions, children report theirs too
+        MessageView::StateChanged(state_changed)
+            if msg.src() == Some(pipeline.upcast_ref::<gst::Object>()) =>
+        {
+            Some(PlayerEvent::StateChanged {
+                old: state_changed.old(),
+                current: state_changed.current(),
+            })
+        }
+        MessageView::DurationChanged(_) => Some(PlayerEvent::DurationChanged),
+        MessageView::StreamStart(_) => Some(PlayerEvent::StreamStart),
+        MessageView::SegmentDone(_) => Some(PlayerEvent::SegmentDone),
+        MessageView::Tag(tag) => Some(PlayerEvent::Tags(tag.tags())),
+        MessageView::StreamCollection(collection) => Some(PlayerEvent::StreamCollection(
+            collection.stream_collection(),
+        )),
+        MessageView::StreamsSelected(selected) => {
+            Some(PlayerEvent::StreamsSelected(selected.streams()))
+        }
+        MessageView::Application(application) => {
+            let structure = application.structure()?;
+            if structure.name() == RECORDING_FINISHED {
+                let location = structure.get::<String>("location").ok()?;
+                Some(PlayerEvent::RecordingFinished(location))
+            } else if structure.name() == TRACKS_CHANGED {
+                Some(PlayerEvent::TracksChanged)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,102 @@
+use gtk::{prelude::*, ApplicationWindow, Button, Orientation};
+
+use crate::{
+    bus::PlayerEvent,
+    encoding::{Container, EncodingOptions, CONTAINER_NAMES},
+    error_banner::ErrorBanner,
+    gstreamer::GstreamerManager,
+    recording::default_location,
+    snapshot::SnapshotOptions,
+    window::add_action,
+    LOG_DOMAIN,
+};
+
+// Snapshot button, record toggle and the container recordings are saved in
+pub struct CaptureControls {
+    container: gtk::Box,
+}
+
+impl CaptureControls {
+    pub fn new(
+        window: &ApplicationWindow,
+        snapshot_options: SnapshotOptions,
+        gst_manager: &GstreamerManager,
+        error_banner: ErrorBanner,
+    ) -> Self {
+        let snapshot_button = Button::with_label("📷 Snapshot");
+        snapshot_button.set_valign(gtk::Align::Center);
+        let record_toggle = gtk::ToggleButton::with_label("⏺ Record");
+        record_toggle.set_valign(gtk::Align::Center);
+        let container_selector = gtk::DropDown::from_strings(CONTAINER_NAMES);
+        container_selector.set_valign(gtk::Align::Center);
+
+        let container = gtk::Box::new(Orientation::Horizontal, 5);
+        container.append(&snapshot_button);
+        container.append(&record_toggle);
+        container.append(&container_selector);
+
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = error_banner.clone();
+        add_action(window, "snapshot", move || {
+            let result = gst_manager_clone
+                .snapshot()
+                .and_then(|frame| frame.save(&snapshot_options));
+            if let Err(err) = result {
+                error_banner_clone.show(&err);
+            }
+        });
+        snapshot_button.set_action_name(Some("win.snapshot"));
+
+        let record_toggle_clone = record_toggle.clone();
+        add_action(window, "record", move || {
+            record_toggle_clone.set_active(!record_toggle_clone.is_active());
+        });
+
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = error_banner.clone();
+        let container_selector_clone = container_selector.clone();
+        record_toggle.connect_toggled(move |record_toggle| {
+            if !record_toggle.is_active() {
+                gst_manager_clone.stop_recording();
+                record_toggle.set_label("⏺ Record");
+                container_selector_clone.set_sensitive(true);
+                return;
+            }
+            if gst_manager_clone.is_recording() {
+                return;
+            }
+
+            let container = CONTAINER_NAMES
+                .get(container_selector_clone.selected() as usize)
+                .and_then(|name| name.parse().ok())
+                .unwrap_or(Container::Mp4);
+            let location = default_location(container);
+            match gst_manager_clone
+                .start_recording(&EncodingOptions::for_container(container), &location)
+            {
+                Ok(()) => {
+                    record_toggle.set_label("⏹ Stop recording");
+                    container_selector_clone.set_sensitive(false);
+                }
+                Err(err) => {
+                    error_banner_clone.show(&err);
+                    record_toggle.set_active(false);
+                }
+            }
+        });
+
+        // The branch also finishes by itself when the stream ends
+        gst_manager.bus.subscribe(move |event| {
+            if let PlayerEvent::RecordingFinished(location) = event {
+                glib::g_info!(LOG_DOMAIN, "Saved recording '{location}'");
+                record_toggle.set_active(false);
+            }
+        });
+
+        Self { container }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+}
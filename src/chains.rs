@@ -0,0 +1,81 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use toml::{Table, Value};
+
+use crate::{
+    config::{config_file, read_table, write_table},
+    error::GstreamerError,
+};
+
+const CHAINS_FILE: &str = "chains.toml";
+
+// Named filter stacks, stored as
+//
+// [chains]
+// retro = ["agingtv", "edgetv"]
+pub struct ChainStore {
+    path: PathBuf,
+    chains: BTreeMap<String, Vec<String>>,
+}
+
+impl ChainStore {
+    pub fn load() -> Result<Self, GstreamerError> {
+        let path = config_file(CHAINS_FILE)?;
+        let table = read_table(&path)?;
+
+        let chains = table
+            .get("chains")
+            .and_then(Value::as_table)
+            .map(|chains| {
+                chains
+                    .iter()
+                    .filter_map(|(name, filters)| {
+                        let filters = filters
+                            .as_array()?
+                            .iter()
+                            .filter_map(|filter| filter.as_str().map(str::to_string))
+                            .collect();
+                        Some((name.clone(), filters))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self { path, chains })
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.chains.keys().map(String::as_str).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.chains.get(name).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, name: &str, filters: Vec<String>) -> Result<(), GstreamerError> {
+        self.chains.insert(name.to_string(), filters);
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), GstreamerError> {
+        if self.chains.remove(name).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), GstreamerError> {
+        let chains: Table = self
+            .chains
+            .iter()
+            .map(|(name, filters)| {
+                let filters = filters.iter().cloned().map(Value::String).collect();
+                (name.clone(), Value::Array(filters))
+            })
+            .collect();
+
+        let mut table = Table::new();
+        table.insert("chains".to_string(), Value::Table(chains));
+        write_table(&self.path, &table)
+    }
+}
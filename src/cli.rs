@@ -0,0 +1,192 @@
+use glib::{OptionArg, OptionFlags, VariantDict};
+use gtk::{prelude::*, Application};
+
+use crate::{
+    encoding::{
+        AudioCodec, Container, EncodingOptions, VideoCodec, AUDIO_CODEC_NAMES, CONTAINER_NAMES,
+        VIDEO_CODEC_NAMES,
+    },
+    gstreamer::{Backend, BACKEND_NAMES},
+    render::RenderOptions,
+    snapshot::{SnapshotOptions, SNAPSHOT_FORMAT_NAMES},
+};
+
+pub fn add_options(app: &Application) {
+    let string_options = [
+        (
+            "backend",
+            'b',
+            format!("Player backend ({})", BACKEND_NAMES.join(", ")),
+            "BACKEND",
+        ),
+        (
+            "render",
+            'r',
+            "Render a file with a filter applied instead of opening a window".to_string(),
+            "INPUT",
+        ),
+        (
+            "probe",
+            'p',
+            "Print the media information of a file as JSON instead of opening a window".to_string(),
+            "INPUT",
+        ),
+        (
+            "output",
+            'o',
+            "Output file for --render".to_string(),
+            "FILE",
+        ),
+        (
+            "filter",
+            'f',
+            "Comma separated filter chain applied by --render".to_string(),
+            "FILTERS",
+        ),
+        (
+            "container",
+            '\0',
+            format!("Container for --render ({})", CONTAINER_NAMES.join(", ")),
+            "CONTAINER",
+        ),
+        (
+            "video-codec",
+            '\0',
+            format!(
+                "Video codec for --render ({})",
+                VIDEO_CODEC_NAMES.join(", ")
+            ),
+            "CODEC",
+        ),
+        (
+            "audio-codec",
+            '\0',
+            format!(
+                "Audio codec for --render ({})",
+                AUDIO_CODEC_NAMES.join(", ")
+            ),
+            "CODEC",
+        ),
+        (
+            "snapshot-dir",
+            '\0',
+            "Folder snapshots are saved to".to_string(),
+            "DIR",
+        ),
+        (
+            "snapshot-format",
+            '\0',
+            format!(
+                "Image format of snapshots ({})",
+                SNAPSHOT_FORMAT_NAMES.join(", ")
+            ),
+            "FORMAT",
+        ),
+    ];
+    for (long_name, short_name, description, arg_description) in string_options {
+        app.add_main_option(
+            long_name,
+            glib::Char::from(short_name as u8),
+            OptionFlags::NONE,
+            OptionArg::String,
+            &description,
+            Some(arg_description),
+        );
+    }
+
+    let int_options = [
+        ("video-bitrate", "Video bitrate in kbit/s for --render"),
+        ("audio-bitrate", "Audio bitrate in kbit/s for --render"),
+    ];
+    for (long_name, description) in int_options {
+        app.add_main_option(
+            long_name,
+            glib::Char::from(0),
+            OptionFlags::NONE,
+            OptionArg::Int,
+            description,
+            Some("KBPS"),
+        );
+    }
+}
+
+pub fn backend(options: &VariantDict) -> Result<Option<Backend>, String> {
+    string_option(options, "backend")?
+        .map(|name| name.parse())
+        .transpose()
+}
+
+pub fn render_options(options: &VariantDict) -> Result<Option<RenderOptions>, String> {
+    let Some(input) = string_option(options, "render")? else {
+        return Ok(None);
+    };
+    let output = string_option(options, "output")?
+        .ok_or_else(|| "--render needs an --output file".to_string())?;
+
+    let container = match string_option(options, "container")? {
+        Some(name) => name.parse()?,
+        None => Container::from_path(&output).unwrap_or(Container::Mp4),
+    };
+    let mut encoding = EncodingOptions::for_container(container);
+    if let Some(name) = string_option(options, "video-codec")? {
+        encoding.video_codec = name.parse::<VideoCodec>()?;
+    }
+    if let Some(name) = string_option(options, "audio-codec")? {
+        encoding.audio_codec = name.parse::<AudioCodec>()?;
+    }
+    if let Some(kbps) = bitrate_option(options, "video-bitrate")? {
+        encoding.video_bitrate_kbps = kbps;
+    }
+    if let Some(kbps) = bitrate_option(options, "audio-bitrate")? {
+        encoding.audio_bitrate_kbps = kbps;
+    }
+
+    Ok(Some(RenderOptions {
+        input,
+        output,
+        filters: string_option(options, "filter")?
+            .map(|filters| {
+                filters
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        encoding,
+    }))
+}
+
+pub fn probe_input(options: &VariantDict) -> Result<Option<String>, String> {
+    string_option(options, "probe")
+}
+
+pub fn snapshot_options(options: &VariantDict) -> Result<SnapshotOptions, String> {
+    let mut snapshot_options = SnapshotOptions::default();
+    if let Some(folder) = string_option(options, "snapshot-dir")? {
+        snapshot_options.folder = folder.into();
+    }
+    if let Some(name) = string_option(options, "snapshot-format")? {
+        snapshot_options.format = name.parse()?;
+    }
+    Ok(snapshot_options)
+}
+
+fn string_option(options: &VariantDict, name: &str) -> Result<Option<String>, String> {
+    options
+        .lookup::<String>(name)
+        .map_err(|err| format!("--{name}: {err}"))
+}
+
+fn bitrate_option(options: &VariantDict, name: &str) -> Result<Option<u32>, String> {
+    let Some(kbps) = options
+        .lookup::<i32>(name)
+        .map_err(|err| format!("--{name}: {err}"))?
+    else {
+        return Ok(None);
+    };
+    u32::try_from(kbps)
+        .ok()
+        .filter(|&kbps| kbps > 0)
+        .map(Some)
+        .ok_or_else(|| format!("--{name} must be a positive number of kbit/s"))
+}
@@ -0,0 +1,50 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml::Table;
+
+use crate::error::GstreamerError;
+
+const APP_DIR: &str = "gstreamer-rust-tut";
+
+pub fn config_file(file_name: &str) -> Result<PathBuf, GstreamerError> {
+    dirs::config_dir()
+        .map(|dir| dir.join(APP_DIR).join(file_name))
+        .ok_or_else(|| GstreamerError::Config {
+            path: file_name.to_string(),
+            reason: "no config directory on this system".to_string(),
+        })
+}
+
+// A file that doesn't exist yet reads as an empty table
+pub fn read_table(path: &Path) -> Result<Table, GstreamerError> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+    read_existing_table(path)
+}
+
+// For files the user picked, where a missing file is an error
+pub fn read_existing_table(path: &Path) -> Result<Table, GstreamerError> {
+    let config_error = |reason: String| GstreamerError::Config {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let text = fs::read_to_string(path).map_err(|err| config_error(err.to_string()))?;
+    text.parse().map_err(|err| config_error(format!("{err}")))
+}
+
+pub fn write_table(path: &Path, table: &Table) -> Result<(), GstreamerError> {
+    let config_error = |reason: String| GstreamerError::Config {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).map_err(|err| config_error(err.to_string()))?;
+    }
+    fs::write(path, table.to_string()).map_err(|err| config_error(err.to_string()))
+}
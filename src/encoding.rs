@@ -0,0 +1,307 @@
+use std::{path::Path, str::FromStr};
+
+use gst::{prelude::*, Element};
+
+use crate::{
+    error::GstreamerError,
+    gstreamer::{make_element, set_element_property, set_element_property_from_str},
+};
+
+const QUEUE: &str = "queue";
+const VIDEO_CONVERT: &str = "videoconvert";
+const AUDIO_CONVERT: &str = "audioconvert";
+const AUDIO_RESAMPLE: &str = "audioresample";
+const FILE_SINK: &str = "filesink";
+
+pub const CONTAINER_NAMES: &[&str] = &["mp4", "mkv", "webm"];
+pub const VIDEO_CODEC_NAMES: &[&str] = &["h264", "vp8", "vp9"];
+pub const AUDIO_CODEC_NAMES: &[&str] = &["aac", "opus", "vorbis"];
+
+const DEFAULT_VIDEO_BITRATE_KBPS: u32 = 2048;
+const DEFAULT_AUDIO_BITRATE_KBPS: u32 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Mp4,
+    Mkv,
+    Webm,
+}
+
+impl Container {
+    pub fn from_path(path: &str) -> Option<Self> {
+        Path::new(path).extension()?.to_str()?.parse().ok()
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+            Container::Webm => "webm",
+        }
+    }
+
+    fn muxer(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4mux",
+            Container::Mkv => "matroskamux",
+            Container::Webm => "webmmux",
+        }
+    }
+
+    fn default_video_codec(&self) -> VideoCodec {
+        match self {
+            Container::Mp4 | Container::Mkv => VideoCodec::H264,
+            Container::Webm => VideoCodec::Vp8,
+        }
+    }
+
+    fn default_audio_codec(&self) -> AudioCodec {
+        match self {
+            Container::Mp4 => AudioCodec::Aac,
+            Container::Mkv | Container::Webm => AudioCodec::Opus,
+        }
+    }
+
+    fn supports(&self, video_codec: VideoCodec, audio_codec: AudioCodec) -> bool {
+        match self {
+            Container::Mkv => true,
+            Container::Mp4 => video_codec == VideoCodec::H264 && audio_codec != AudioCodec::Vorbis,
+            Container::Webm => video_codec != VideoCodec::H264 && audio_codec != AudioCodec::Aac,
+        }
+    }
+}
+
+impl FromStr for Container {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "mp4" => Ok(Container::Mp4),
+            "mkv" => Ok(Container::Mkv),
+            "webm" => Ok(Container::Webm),
+            _ => Err(format!(
+                "Unknown container '{name}', expected one of: {}",
+                CONTAINER_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    Vp8,
+    Vp9,
+}
+
+impl VideoCodec {
+    fn encoder(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "x264enc",
+            VideoCodec::Vp8 => "vp8enc",
+            VideoCodec::Vp9 => "vp9enc",
+        }
+    }
+
+    // Settings that keep the encoder up with a playing pipeline
+    fn realtime_properties(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            VideoCodec::H264 => &[("tune", "zerolatency"), ("speed-preset", "ultrafast")],
+            VideoCodec::Vp8 | VideoCodec::Vp9 => &[("deadline", "1"), ("cpu-used", "8")],
+        }
+    }
+
+    // x264enc takes kbit/s, the vpx encoders bit/s
+    fn bitrate_property(&self, kbps: u32) -> (&'static str, u64) {
+        match self {
+            VideoCodec::H264 => ("bitrate", u64::from(kbps)),
+            VideoCodec::Vp8 | VideoCodec::Vp9 => ("target-bitrate", u64::from(kbps) * 1000),
+        }
+    }
+}
+
+impl FromStr for VideoCodec {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "h264" | "x264" => Ok(VideoCodec::H264),
+            "vp8" => Ok(VideoCodec::Vp8),
+            "vp9" => Ok(VideoCodec::Vp9),
+            _ => Err(format!(
+                "Unknown video codec '{name}', expected one of: {}",
+                VIDEO_CODEC_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCodec {
+    Aac,
+    Opus,
+    Vorbis,
+}
+
+impl AudioCodec {
+    fn encoder(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "avenc_aac",
+            AudioCodec::Opus => "opusenc",
+            AudioCodec::Vorbis => "vorbisenc",
+        }
+    }
+}
+
+impl FromStr for AudioCodec {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "aac" => Ok(AudioCodec::Aac),
+            "opus" => Ok(AudioCodec::Opus),
+            "vorbis" => Ok(AudioCodec::Vorbis),
+            _ => Err(format!(
+                "Unknown audio codec '{name}', expected one of: {}",
+                AUDIO_CODEC_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodingOptions {
+    pub container: Container,
+    pub video_codec: VideoCodec,
+    pub audio_codec: AudioCodec,
+    pub video_bitrate_kbps: u32,
+    pub audio_bitrate_kbps: u32,
+}
+
+impl EncodingOptions {
+    pub fn for_container(container: Container) -> Self {
+        Self {
+            container,
+            video_codec: container.default_video_codec(),
+            audio_codec: container.default_audio_codec(),
+            video_bitrate_kbps: DEFAULT_VIDEO_BITRATE_KBPS,
+            audio_bitrate_kbps: DEFAULT_AUDIO_BITRATE_KBPS,
+        }
+    }
+}
+
+impl Default for EncodingOptions {
+    fn default() -> Self {
+        Self::for_container(Container::Mp4)
+    }
+}
+
+// queue ! videoconvert ! encoder ─┐
+//                                 ├─ muxer ! filesink
+// queue ! audioconvert ! encoder ─┘
+pub struct EncodingBranch {
+    pub video_entry: Element,
+    pub audio_entry: Element,
+    pub file_sink: Element,
+}
+
+impl EncodingBranch {
+    pub fn new(
+        bin: &gst::Bin,
+        options: &EncodingOptions,
+        location: &str,
+    ) -> Result<Self, GstreamerError> {
+        Self::build(bin, options, location, false)
+    }
+
+    pub fn new_live(
+        bin: &gst::Bin,
+        options: &EncodingOptions,
+        location: &str,
+    ) -> Result<Self, GstreamerError> {
+        Self::build(bin, options, location, true)
+    }
+
+    fn build(
+        bin: &gst::Bin,
+        options: &EncodingOptions,
+        location: &str,
+        live: bool,
+    ) -> Result<Self, GstreamerError> {
+        if !options
+            .container
+            .supports(options.video_codec, options.audio_codec)
+        {
+            return Err(GstreamerError::Unsupported(format!(
+                "{:?} can't hold {:?} video with {:?} audio",
+                options.container, options.video_codec, options.audio_codec
+            )));
+        }
+
+        let video_queue = make_element(QUEUE)?;
+        let video_convert = make_element(VIDEO_CONVERT)?;
+        let video_encoder = make_element(options.video_codec.encoder())?;
+        let audio_queue = make_element(QUEUE)?;
+        let audio_convert = make_element(AUDIO_CONVERT)?;
+        let audio_resample = make_element(AUDIO_RESAMPLE)?;
+        let audio_encoder = make_element(options.audio_codec.encoder())?;
+        let muxer = make_element(options.container.muxer())?;
+        let file_sink = make_element(FILE_SINK)?;
+
+        let (video_bitrate_property, video_bitrate) = options
+            .video_codec
+            .bitrate_property(options.video_bitrate_kbps);
+        set_element_property_from_str(
+            &video_encoder,
+            video_bitrate_property,
+            &video_bitrate.to_string(),
+        )?;
+        set_element_property_from_str(
+            &audio_encoder,
+            "bitrate",
+            &(u64::from(options.audio_bitrate_kbps) * 1000).to_string(),
+        )?;
+        set_element_property(&file_sink, "location", location)?;
+        if live {
+            for (property, value) in options.video_codec.realtime_properties() {
+                set_element_property_from_str(&video_encoder, property, value)?;
+            }
+            // Don't make a playing pipeline wait for the new branch to preroll
+            set_element_property(&file_sink, "async", false)?;
+        }
+
+        bin.add_many([
+            &video_queue,
+            &video_convert,
+            &video_encoder,
+            &audio_queue,
+            &audio_convert,
+            &audio_resample,
+            &audio_encoder,
+            &muxer,
+            &file_sink,
+        ])?;
+
+        Element::link_many([&video_queue, &video_convert, &video_encoder, &muxer]).map_err(
+            |_| GstreamerError::Link(format!("video encoder → {}", options.container.muxer())),
+        )?;
+        Element::link_many([
+            &audio_queue,
+            &audio_convert,
+            &audio_resample,
+            &audio_encoder,
+            &muxer,
+        ])
+        .map_err(|_| {
+            GstreamerError::Link(format!("audio encoder → {}", options.container.muxer()))
+        })?;
+        Element::link_many([&muxer, &file_sink])
+            .map_err(|_| GstreamerError::Link("muxer → filesink".to_string()))?;
+
+        Ok(Self {
+            video_entry: video_queue,
+            audio_entry: audio_queue,
+            file_sink,
+        })
+    }
+}
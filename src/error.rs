@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GstreamerError {
+    #[error("Could not create '{0}', is the plugin that provides it installed?")]
+    MissingElement(String),
+    #[error("Link {0} failed")]
+    Link(String),
+    #[error("Failed to set {element} to {state:?}")]
+    StateChange { element: String, state: gst::State },
+    #[error("Property '{property}' of {element}: {reason}")]
+    Property {
+        element: String,
+        property: String,
+        reason: String,
+    },
+    #[error("Invalid URI '{uri}': {reason}")]
+    InvalidUri { uri: String, reason: String },
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("Failed to relink filters: {0}")]
+    Relink(String),
+    #[error("Snapshot failed: {0}")]
+    Snapshot(String),
+    #[error("Media analysis failed: {0}")]
+    Discovery(String),
+    #[error("Config file '{path}': {reason}")]
+    Config { path: String, reason: String },
+    #[error("Pipeline error: {0}")]
+    Pipeline(#[from] glib::BoolError),
+}
@@ -0,0 +1,54 @@
+use std::fmt::Display;
+
+use gtk::{prelude::*, Button, Orientation};
+
+use crate::LOG_DOMAIN;
+
+#[derive(Clone)]
+pub struct ErrorBanner {
+    revealer: gtk::Revealer,
+    label: gtk::Label,
+}
+
+impl ErrorBanner {
+    pub fn new() -> Self {
+        let label = gtk::Label::new(None);
+        label.set_hexpand(true);
+        label.set_halign(gtk::Align::Start);
+        label.set_wrap(true);
+
+        let close_button = Button::with_label("✕");
+        close_button.set_valign(gtk::Align::Center);
+
+        let banner_box = gtk::Box::new(Orientation::Horizontal, 5);
+        banner_box.set_css_classes(&["error-banner"]);
+        banner_box.append(&label);
+        banner_box.append(&close_button);
+
+        let revealer = gtk::Revealer::new();
+        revealer.set_transition_type(gtk::RevealerTransitionType::SlideDown);
+        revealer.set_child(Some(&banner_box));
+        revealer.set_reveal_child(false);
+
+        let revealer_clone = revealer.clone();
+        close_button.connect_clicked(move |_| {
+            revealer_clone.set_reveal_child(false);
+        });
+
+        Self { revealer, label }
+    }
+
+    pub fn widget(&self) -> &gtk::Revealer {
+        &self.revealer
+    }
+
+    pub fn show(&self, err: &impl Display) {
+        glib::g_warning!(LOG_DOMAIN, "{err}");
+        self.label.set_text(&err.to_string());
+        self.revealer.set_reveal_child(true);
+    }
+
+    pub fn hide(&self) {
+        self.revealer.set_reveal_child(false);
+    }
+}
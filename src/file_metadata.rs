@@ -1,18 +1,579 @@
 use std::fs::metadata;
 use std::fs::Metadata;
 use std::path::PathBuf;
+use std::time::SystemTime;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use gst::prelude::*;
+use gstreamer_pbutils::{
+    prelude::*, Discoverer, DiscovererAudioInfo, DiscovererContainerInfo, DiscovererStreamInfo,
+    DiscovererSubtitleInfo, DiscovererVideoInfo,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::GstreamerError,
+    source::{local_path, to_uri},
+    LOG_DOMAIN,
+};
+
+// Network sources can take a while before they report their streams
+const DISCOVER_TIMEOUT: gst::ClockTime = gst::ClockTime::from_seconds(15);
+// Bumped whenever a key of the JSON report is renamed, removed or changes type
+pub const PROBE_SCHEMA_VERSION: u32 = 1;
 
 #[derive(Debug)]
 pub struct FileMetadata {
-    pub path: PathBuf,
-    pub metadata: Metadata,
+    pub uri: String,
+    pub path: Option<PathBuf>,
+    pub metadata: Option<Metadata>,
 }
 
 impl FileMetadata {
-    pub fn new(video_path: &str) -> Self {
+    pub fn new(uri: &str) -> Self {
+        let path = local_path(uri);
+        // Network streams have no filesystem metadata
+        let metadata = path.as_ref().and_then(|path| match metadata(path) {
+            Ok(metadata) => Some(metadata),
+            Err(err) => {
+                glib::g_warning!(LOG_DOMAIN, "Error extracting file metadata: {err}");
+                None
+            }
+        });
+
         Self {
-            path: PathBuf::from(video_path),
-            metadata: metadata(video_path).expect("Error extracting file metadata"),
+            uri: uri.to_string(),
+            path,
+            metadata,
+        }
+    }
+
+    // Blocks until the Discoverer has prerolled the media, so call it off the
+    // main thread
+    pub fn discover(uri: &str) -> Result<MediaInfo, GstreamerError> {
+        let uri = to_uri(uri)?;
+        let discoverer = Discoverer::new(DISCOVER_TIMEOUT)
+            .map_err(|err| GstreamerError::Discovery(err.to_string()))?;
+        let info = discoverer
+            .discover_uri(&uri)
+            .map_err(|err| GstreamerError::Discovery(err.to_string()))?;
+
+        let container = info
+            .stream_info()
+            .filter(|stream| stream.is::<DiscovererContainerInfo>())
+            .and_then(|stream| codec_description(&stream));
+        let streams = info
+            .stream_list()
+            .iter()
+            .filter_map(StreamInfo::from_discoverer)
+            .collect();
+
+        Ok(MediaInfo {
+            container,
+            duration: info.duration(),
+            seekable: info.is_seekable(),
+            live: info.is_live(),
+            tags: tag_pairs(info.tags()),
+            streams,
+        })
+    }
+
+    // The --probe report. Every key is always present, null when unknown, so
+    // tools can rely on the layout; `error` is set when discovery failed.
+    pub fn to_json(&self, media: &Result<MediaInfo, GstreamerError>) -> Value {
+        let file = self.metadata.as_ref().map(|metadata| {
+            let time = |time: std::io::Result<SystemTime>| {
+                time.ok().map(|time| {
+                    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+                })
+            };
+            let file_type = metadata.file_type();
+            let file_type = if file_type.is_dir() {
+                "directory"
+            } else if file_type.is_file() {
+                "file"
+            } else if file_type.is_symlink() {
+                "symlink"
+            } else {
+                "unknown"
+            };
+            json!({
+                "type": file_type,
+                "size": metadata.len(),
+                "created": time(metadata.created()),
+                "modified": time(metadata.modified()),
+                "accessed": time(metadata.accessed()),
+            })
+        });
+
+        json!({
+            "schema_version": PROBE_SCHEMA_VERSION,
+            "uri": self.uri,
+            "path": self.path.as_ref().map(|path| path.to_string_lossy()),
+            "file": file,
+            "media": media.as_ref().ok().map(MediaInfo::to_json),
+            "error": media.as_ref().err().map(|err| err.to_string()),
+        })
+    }
+}
+
+// What the Discoverer found out about a file or stream
+#[derive(Debug, Clone)]
+pub struct MediaInfo {
+    pub container: Option<String>,
+    pub duration: Option<gst::ClockTime>,
+    pub seekable: bool,
+    pub live: bool,
+    pub tags: Vec<(String, String)>,
+    pub streams: Vec<StreamInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamInfo {
+    pub kind: StreamKind,
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    // Bits per second, when the stream or its tags tell
+    pub bitrate: Option<u32>,
+    pub tags: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StreamKind {
+    Video {
+        width: u32,
+        height: u32,
+        // Numerator and denominator, None for still images and variable rates
+        framerate: Option<(i32, i32)>,
+        interlaced: bool,
+    },
+    Audio {
+        sample_rate: u32,
+        channels: u32,
+        depth: u32,
+    },
+    Subtitle,
+}
+
+impl MediaInfo {
+    pub fn rows(&self) -> Vec<(String, String)> {
+        let mut rows = vec![(
+            "Container".to_string(),
+            self.container.clone().unwrap_or_else(|| "None".to_string()),
+        )];
+        if let Some(duration) = self.duration {
+            rows.push(("Duration".to_string(), format_duration(duration)));
         }
+        rows.push(("Seekable".to_string(), yes_no(self.seekable)));
+        rows.push(("Live".to_string(), yes_no(self.live)));
+        rows
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "container": self.container,
+            "duration_ns": self.duration.map(gst::ClockTime::nseconds),
+            "seekable": self.seekable,
+            "live": self.live,
+            "tags": tags_json(&self.tags),
+            "streams": self.streams.iter().map(StreamInfo::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl StreamInfo {
+    fn from_discoverer(stream: &DiscovererStreamInfo) -> Option<Self> {
+        let kind = if let Some(video) = stream.downcast_ref::<DiscovererVideoInfo>() {
+            let framerate = video.framerate();
+            StreamKind::Video {
+                width: video.width(),
+                height: video.height(),
+                framerate: (framerate.numer() > 0 && framerate.denom() > 0)
+                    .then(|| (framerate.numer(), framerate.denom())),
+                interlaced: video.is_interlaced(),
+            }
+        } else if let Some(audio) = stream.downcast_ref::<DiscovererAudioInfo>() {
+            StreamKind::Audio {
+                sample_rate: audio.sample_rate(),
+                channels: audio.channels(),
+                depth: audio.depth(),
+            }
+        } else if stream.is::<DiscovererSubtitleInfo>() {
+            StreamKind::Subtitle
+        } else {
+            return None;
+        };
+
+        let language = if let Some(audio) = stream.downcast_ref::<DiscovererAudioInfo>() {
+            audio.language()
+        } else if let Some(subtitle) = stream.downcast_ref::<DiscovererSubtitleInfo>() {
+            subtitle.language()
+        } else {
+            None
+        };
+        let bitrate = if let Some(video) = stream.downcast_ref::<DiscovererVideoInfo>() {
+            Some(video.bitrate())
+        } else {
+            stream
+                .downcast_ref::<DiscovererAudioInfo>()
+                .map(|audio| audio.bitrate())
+        };
+        let tags = stream.tags();
+        let bitrate = bitrate.filter(|&bitrate| bitrate > 0).or_else(|| {
+            let tags = tags.as_ref()?;
+            tags.get::<gst::tags::Bitrate>()
+                .or_else(|| tags.get::<gst::tags::NominalBitrate>())
+                .map(|bitrate| bitrate.get())
+        });
+
+        Some(Self {
+            kind,
+            codec: codec_description(stream),
+            language: language.map(|language| language.to_string()),
+            bitrate,
+            tags: tag_pairs(tags),
+        })
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            StreamKind::Video { .. } => "video",
+            StreamKind::Audio { .. } => "audio",
+            StreamKind::Subtitle => "subtitle",
+        }
+    }
+
+    pub fn rows(&self) -> Vec<(String, String)> {
+        let mut rows = Vec::new();
+        if let Some(codec) = &self.codec {
+            rows.push(("Codec".to_string(), codec.clone()));
+        }
+        match &self.kind {
+            StreamKind::Video {
+                width,
+                height,
+                framerate,
+                interlaced,
+            } => {
+                rows.push(("Resolution".to_string(), format!("{width}×{height}")));
+                if let Some((numer, denom)) = framerate {
+                    rows.push((
+                        "Framerate".to_string(),
+                        format!("{:.3} fps", *numer as f64 / *denom as f64),
+                    ));
+                }
+                rows.push(("Interlaced".to_string(), yes_no(*interlaced)));
+            }
+            StreamKind::Audio {
+                sample_rate,
+                channels,
+                depth,
+            } => {
+                rows.push(("Sample rate".to_string(), format!("{sample_rate} Hz")));
+                rows.push(("Channels".to_string(), channels.to_string()));
+                if *depth > 0 {
+                    rows.push(("Depth".to_string(), format!("{depth} bit")));
+                }
+            }
+            StreamKind::Subtitle => {}
+        }
+        if let Some(bitrate) = self.bitrate {
+            rows.push(("Bitrate".to_string(), format!("{} kbit/s", bitrate / 1000)));
+        }
+        if let Some(language) = &self.language {
+            rows.push(("Language".to_string(), language.clone()));
+        }
+        rows
+    }
+
+    // The keys common to every stream come first, then those of its type
+    fn to_json(&self) -> Value {
+        let mut fields = Map::new();
+        fields.insert("type".to_string(), json!(self.kind_name()));
+        fields.insert("codec".to_string(), json!(self.codec));
+        fields.insert("language".to_string(), json!(self.language));
+        fields.insert("bitrate".to_string(), json!(self.bitrate));
+        let kind_fields = match &self.kind {
+            StreamKind::Video {
+                width,
+                height,
+                framerate,
+                interlaced,
+            } => json!({
+                "width": width,
+                "height": height,
+                "framerate": framerate.map(|(numer, denom)| json!({
+                    "numerator": numer,
+                    "denominator": denom,
+                })),
+                "interlaced": interlaced,
+            }),
+            StreamKind::Audio {
+                sample_rate,
+                channels,
+                depth,
+            } => json!({
+                "sample_rate": sample_rate,
+                "channels": channels,
+                "depth": depth,
+            }),
+            StreamKind::Subtitle => json!({}),
+        };
+        if let Value::Object(kind_fields) = kind_fields {
+            fields.extend(kind_fields);
+        }
+        fields.insert("tags".to_string(), tags_json(&self.tags));
+        Value::Object(fields)
+    }
+}
+
+fn codec_description(stream: &DiscovererStreamInfo) -> Option<String> {
+    let caps = stream.caps()?;
+    Some(gstreamer_pbutils::pb_utils_get_codec_description(&caps).to_string())
+}
+
+// Every tag as text, except images like cover art
+fn tag_pairs(tags: Option<gst::TagList>) -> Vec<(String, String)> {
+    let Some(tags) = tags else {
+        return Vec::new();
+    };
+    tags.iter()
+        .filter(|(_, value)| value.type_() != gst::Sample::static_type())
+        .filter_map(|(name, value)| {
+            let text = value
+                .get::<String>()
+                .ok()
+                .or_else(|| value.serialize().ok().map(|text| text.to_string()))?;
+            Some((name.to_string(), text))
+        })
+        .collect()
+}
+
+// Tag names are unique within a tag list
+fn tags_json(tags: &[(String, String)]) -> Value {
+    Value::Object(
+        tags.iter()
+            .map(|(name, value)| (name.clone(), json!(value)))
+            .collect(),
+    )
+}
+
+fn format_duration(duration: gst::ClockTime) -> String {
+    let seconds = duration.seconds();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        duration.mseconds() % 1000
+    )
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "Yes" } else { "No" }.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_media;
+
+    // What a key holds, `OrNull` for keys that are null when unknown
+    #[derive(Clone, Copy)]
+    enum Kind {
+        Bool,
+        Number,
+        String,
+        Array,
+        Object,
+        OrNull(&'static Kind),
+    }
+
+    fn matches(value: &Value, kind: Kind) -> bool {
+        match kind {
+            Kind::Bool => value.is_boolean(),
+            Kind::Number => value.is_u64() || value.is_i64(),
+            Kind::String => value.is_string(),
+            Kind::Array => value.is_array(),
+            Kind::Object => value.is_object(),
+            Kind::OrNull(kind) => value.is_null() || matches(value, *kind),
+        }
+    }
+
+    // Exactly these keys, in this order, with these kinds
+    fn assert_layout(value: &Value, layout: &[(&str, Kind)]) {
+        let object = value.as_object().expect("an object");
+        let keys: Vec<&str> = object.keys().map(String::as_str).collect();
+        let expected: Vec<&str> = layout.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, expected);
+        for (key, kind) in layout {
+            assert!(matches(&object[*key], *kind), "{key} is {}", object[*key]);
+        }
+    }
+
+    const REPORT: &[(&str, Kind)] = &[
+        ("schema_version", Kind::Number),
+        ("uri", Kind::String),
+        ("path", Kind::OrNull(&Kind::String)),
+        ("file", Kind::OrNull(&Kind::Object)),
+        ("media", Kind::OrNull(&Kind::Object)),
+        ("error", Kind::OrNull(&Kind::String)),
+    ];
+
+    fn probe(uri: &str) -> Value {
+        let media = FileMetadata::discover(uri);
+        let report = FileMetadata::new(uri).to_json(&media);
+        // What --probe prints parses back to the same report
+        let printed = serde_json::to_string_pretty(&report).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&printed).unwrap(), report);
+        assert_layout(&report, REPORT);
+        report
+    }
+
+    #[test]
+    fn generated_clip_fills_every_key() {
+        test_media::init();
+        let path = test_media::write_clip("probe.mkv");
+        let report = probe(&test_media::uri(&path));
+
+        assert_eq!(report["schema_version"], PROBE_SCHEMA_VERSION);
+        assert_eq!(report["path"], path.to_string_lossy().as_ref());
+        assert!(report["error"].is_null());
+
+        let file = &report["file"];
+        assert_layout(
+            file,
+            &[
+                ("type", Kind::String),
+                ("size", Kind::Number),
+                ("created", Kind::OrNull(&Kind::String)),
+                ("modified", Kind::OrNull(&Kind::String)),
+                ("accessed", Kind::OrNull(&Kind::String)),
+            ],
+        );
+        assert_eq!(file["type"], "file");
+        assert_eq!(file["size"], fs::metadata(&path).unwrap().len());
+
+        let media = &report["media"];
+        assert_layout(
+            media,
+            &[
+                ("container", Kind::OrNull(&Kind::String)),
+                ("duration_ns", Kind::OrNull(&Kind::Number)),
+                ("seekable", Kind::Bool),
+                ("live", Kind::Bool),
+                ("tags", Kind::Object),
+                ("streams", Kind::Array),
+            ],
+        );
+        assert_eq!(media["live"], false);
+        assert_eq!(media["tags"]["title"], test_media::CLIP_TITLE);
+        let duration = media["duration_ns"].as_u64().unwrap();
+        assert!(
+            (900_000_000..1_100_000_000).contains(&duration),
+            "{duration}"
+        );
+
+        let streams = media["streams"].as_array().unwrap();
+        let stream = |kind: &str| {
+            streams
+                .iter()
+                .find(|stream| stream["type"] == kind)
+                .unwrap_or_else(|| panic!("no {kind} stream"))
+        };
+        let common = [
+            ("type", Kind::String),
+            ("codec", Kind::OrNull(&Kind::String)),
+            ("language", Kind::OrNull(&Kind::String)),
+            ("bitrate", Kind::OrNull(&Kind::Number)),
+        ];
+
+        let video = stream("video");
+        let video_layout: Vec<_> = common
+            .into_iter()
+            .chain([
+                ("width", Kind::Number),
+                ("height", Kind::Number),
+                ("framerate", Kind::OrNull(&Kind::Object)),
+                ("interlaced", Kind::Bool),
+                ("tags", Kind::Object),
+            ])
+            .collect();
+        assert_layout(video, &video_layout);
+        assert_eq!(video["width"], test_media::CLIP_WIDTH);
+        assert_eq!(video["height"], test_media::CLIP_HEIGHT);
+        assert_eq!(
+            video["framerate"],
+            json!({"numerator": 30, "denominator": 1})
+        );
+        assert_eq!(video["interlaced"], false);
+
+        let audio = stream("audio");
+        let audio_layout: Vec<_> = common
+            .into_iter()
+            .chain([
+                ("sample_rate", Kind::Number),
+                ("channels", Kind::Number),
+                ("depth", Kind::Number),
+                ("tags", Kind::Object),
+            ])
+            .collect();
+        assert_layout(audio, &audio_layout);
+        assert_eq!(audio["sample_rate"], 44100);
+        assert_eq!(audio["channels"], 2);
+    }
+
+    #[test]
+    fn missing_file_sets_the_error() {
+        test_media::init();
+        let path = test_media::temp_path("no-such-clip.mkv");
+        let report = probe(&test_media::uri(&path));
+
+        assert!(report["path"].is_string());
+        assert!(report["file"].is_null());
+        assert!(report["media"].is_null());
+        assert!(report["error"].is_string());
+    }
+
+    #[test]
+    fn network_streams_have_no_path_or_file() {
+        test_media::init();
+        // Nothing listens on the discard port, so discovery fails right away
+        let report = probe("http://127.0.0.1:9/clip.mkv");
+
+        assert!(report["path"].is_null());
+        assert!(report["file"].is_null());
+        assert!(report["media"].is_null());
+        assert!(report["error"].is_string());
+    }
+
+    #[test]
+    fn tag_values_keep_quotes_and_newlines() {
+        test_media::init();
+        let path = test_media::temp_path("probe-tags.mkv");
+        let title = "Take \"two\"\nback\\slash";
+        let pipeline = gst::parse::launch(&format!(
+            "audiotestsrc num-buffers=10 ! matroskamux name=mux ! filesink location=\"{}\"",
+            path.display()
+        ))
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+        pipeline
+            .by_name("mux")
+            .and_dynamic_cast::<gst::TagSetter>()
+            .unwrap()
+            .add_tag::<gst::tags::Title>(&title, gst::TagMergeMode::Replace);
+        test_media::run_to_eos(&pipeline).unwrap();
+
+        let report = probe(&test_media::uri(&path));
+        assert_eq!(report["media"]["tags"]["title"], title);
+        let printed = serde_json::to_string_pretty(&report).unwrap();
+        assert!(
+            printed.contains(r#""title": "Take \"two\"\nback\\slash""#),
+            "{printed}"
+        );
     }
 }
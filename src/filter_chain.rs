@@ -0,0 +1,534 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use glib::thread_guard::ThreadGuard;
+use gst::{prelude::*, Element, PadProbeReturn, PadProbeType};
+
+use crate::{
+    error::GstreamerError,
+    filters::{FilterStage, NO_FILTER},
+    LOG_DOMAIN,
+};
+
+pub type RelinkDone = Box<dyn FnOnce(Result<(), GstreamerError>)>;
+
+// The filter stages between `head` and `tail` inside `bin`. Relinking happens
+// from an IDLE probe on the head's src pad, so no buffer is ever pushed into
+// a half linked chain while the pipeline is playing.
+//
+// Changes made while a relink is waiting for its probe queue up behind it and
+// are linked by the same probe, each one on top of the previous.
+#[derive(Clone)]
+pub struct FilterChain {
+    bin: gst::Bin,
+    head: Element,
+    tail: Element,
+    // What is linked right now
+    stages: Arc<Mutex<Vec<FilterStage>>>,
+    pending: Arc<Mutex<PendingChanges>>,
+}
+
+#[derive(Default)]
+struct PendingChanges {
+    // The stages once every queued change is linked
+    target: Option<Vec<FilterStage>>,
+    waiters: Vec<Waiter>,
+    // An IDLE probe is on its way to link `target`
+    relinking: bool,
+}
+
+// The caller of a change, told once the change is linked or failed
+struct Waiter {
+    context: glib::MainContext,
+    done: ThreadGuard<RelinkDone>,
+}
+
+impl Waiter {
+    fn new(done: impl FnOnce(Result<(), GstreamerError>) + 'static) -> Self {
+        Self {
+            context: glib::MainContext::ref_thread_default(),
+            done: ThreadGuard::new(Box::new(done)),
+        }
+    }
+
+    // Right away on the caller's thread, otherwise from the caller's main context
+    fn notify(self, result: Result<(), GstreamerError>) {
+        if self.done.is_owner() {
+            (self.done.into_inner())(result);
+            return;
+        }
+        let done = self.done;
+        self.context.spawn(async move {
+            (done.into_inner())(result);
+        });
+    }
+}
+
+impl FilterChain {
+    pub fn new(bin: gst::Bin, head: Element, tail: Element) -> Self {
+        Self {
+            bin,
+            head,
+            tail,
+            stages: Arc::new(Mutex::new(Vec::new())),
+            pending: Arc::new(Mutex::new(PendingChanges::default())),
+        }
+    }
+
+    pub fn stages(&self) -> Vec<FilterStage> {
+        self.stages.lock().unwrap().clone()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.stages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|stage| stage.name.clone())
+            .collect()
+    }
+
+    // Every change calls `done` once it is linked, on the caller's thread.
+    // Indices refer to the chain with the changes queued before it applied.
+    // An error returned right away means the change was not queued.
+    pub fn add(
+        &self,
+        filter_name: &str,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        // Create the new filter first so a missing plugin leaves the chain as it is
+        let stage = FilterStage::new(filter_name)?;
+        self.change(done, |stages| stages.push(stage))
+    }
+
+    pub fn remove(
+        &self,
+        index: usize,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.change(done, |stages| {
+            if index < stages.len() {
+                stages.remove(index);
+            }
+        })
+    }
+
+    pub fn move_stage(
+        &self,
+        from: usize,
+        to: usize,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.change(done, |stages| {
+            if from < stages.len() && to < stages.len() {
+                let stage = stages.remove(from);
+                stages.insert(to, stage);
+            }
+        })
+    }
+
+    pub fn set(
+        &self,
+        filter_names: &[String],
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        let new_stages = filter_names
+            .iter()
+            .filter(|name| name.as_str() != NO_FILTER)
+            .map(|name| FilterStage::new(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.change(done, |stages| *stages = new_stages)
+    }
+
+    pub fn clear(
+        &self,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.change(done, Vec::clear)
+    }
+
+    // For stages that were set up beforehand, e.g. from a preset
+    pub fn replace(
+        &self,
+        new_stages: Vec<FilterStage>,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.change(done, |stages| *stages = new_stages)
+    }
+
+    // Applies `edit` on top of the queued changes and starts a relink unless
+    // one is already waiting for its probe. The probe fires right away when
+    // nothing is flowing (stopped, paused or between buffers), otherwise once
+    // the current buffer has gone through.
+    fn change(
+        &self,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+        edit: impl FnOnce(&mut Vec<FilterStage>),
+    ) -> Result<(), GstreamerError> {
+        let head_pad = self
+            .head
+            .static_pad("src")
+            .ok_or_else(|| glib::bool_error!("{} has no src pad", self.head.name()))?;
+
+        let mut pending = self.pending.lock().unwrap();
+        let mut target = pending.target.take().unwrap_or_else(|| self.stages());
+        edit(&mut target);
+        pending.target = Some(target);
+        pending.waiters.push(Waiter::new(done));
+        if pending.relinking {
+            return Ok(());
+        }
+        pending.relinking = true;
+        drop(pending);
+
+        let chain = self.clone();
+        // An IDLE probe can fire a second time before it is removed
+        let fired = AtomicBool::new(false);
+        head_pad.add_probe(PadProbeType::IDLE, move |_pad, _info| {
+            if !fired.swap(true, Ordering::SeqCst) {
+                chain.relink_pending();
+            }
+            PadProbeReturn::Remove
+        });
+        Ok(())
+    }
+
+    // Links the queued changes until none are left, from the IDLE probe
+    fn relink_pending(&self) {
+        loop {
+            let mut pending = self.pending.lock().unwrap();
+            let waiters = std::mem::take(&mut pending.waiters);
+            let target = pending
+                .target
+                .take()
+                .filter(|target| *target != self.stages());
+            let Some(target) = target else {
+                pending.relinking = false;
+                drop(pending);
+                for waiter in waiters {
+                    waiter.notify(Ok(()));
+                }
+                return;
+            };
+            drop(pending);
+
+            if let Err(err) = self.swap(target) {
+                // Changes queued meanwhile were made on top of the failed one
+                let mut pending = self.pending.lock().unwrap();
+                pending.target = None;
+                pending.relinking = false;
+                let queued = std::mem::take(&mut pending.waiters);
+                drop(pending);
+
+                let message = err.to_string();
+                for waiter in waiters.into_iter().chain(queued) {
+                    waiter.notify(Err(GstreamerError::Relink(message.clone())));
+                }
+                return;
+            }
+            for waiter in waiters {
+                waiter.notify(Ok(()));
+            }
+        }
+    }
+
+    fn swap(&self, stages: Vec<FilterStage>) -> Result<(), GstreamerError> {
+        let mut linked = self.stages.lock().unwrap();
+        let old_chain = self.elements(&linked);
+        let new_chain = self.elements(&stages);
+
+        drain(&linked);
+        Element::unlink_many(&old_chain);
+        // Going through NULL clears the EOS the drain left on the pads
+        for stage in linked.iter() {
+            stage.bin.set_state(gst::State::Null).ok();
+        }
+
+        if let Err(err) = self.link(&linked, &stages, &new_chain) {
+            // Put the previous chain back so playback keeps going
+            Element::unlink_many(&new_chain);
+            for stage in stages.iter().filter(|stage| !linked.contains(stage)) {
+                self.drop_stage(stage).ok();
+            }
+            for stage in linked.iter() {
+                stage.bin.sync_state_with_parent().ok();
+            }
+            Element::link_many(&old_chain).ok();
+            glib::g_warning!(LOG_DOMAIN, "Failed to relink filters into pipeline: {err}");
+            return Err(err);
+        }
+
+        for stage in linked.iter().filter(|stage| !stages.contains(stage)) {
+            if let Err(err) = self.drop_stage(stage) {
+                glib::g_warning!(LOG_DOMAIN, "Failed to drop filter {}: {err}", stage.name);
+            }
+        }
+        *linked = stages;
+        glib::g_debug!(LOG_DOMAIN, "Filter chain: {}", describe(&linked));
+        Ok(())
+    }
+
+    fn link(
+        &self,
+        linked: &[FilterStage],
+        stages: &[FilterStage],
+        chain: &[Element],
+    ) -> Result<(), GstreamerError> {
+        for stage in stages {
+            if !linked.contains(stage) {
+                self.bin.add(&stage.bin)?;
+            }
+            stage.bin.sync_state_with_parent()?;
+        }
+        Element::link_many(chain).map_err(|_| GstreamerError::Link(describe(stages)))
+    }
+
+    fn drop_stage(&self, stage: &FilterStage) -> Result<(), GstreamerError> {
+        stage
+            .bin
+            .set_state(gst::State::Null)
+            .map_err(|_| GstreamerError::StateChange {
+                element: stage.name.clone(),
+                state: gst::State::Null,
+            })?;
+        self.bin.remove(&stage.bin)?;
+        Ok(())
+    }
+
+    fn elements(&self, stages: &[FilterStage]) -> Vec<Element> {
+        std::iter::once(self.head.clone())
+            .chain(stages.iter().map(|stage| stage.bin.clone().upcast()))
+            .chain(std::iter::once(self.tail.clone()))
+            .collect()
+    }
+}
+
+// Pushes EOS through the stages so they let go of any frame they still hold.
+// Stages are plain transforms without queues, so the EOS has gone all the way
+// through by the time `send_event` returns; it is dropped before the tail.
+fn drain(stages: &[FilterStage]) {
+    let (Some(first), Some(last)) = (stages.first(), stages.last()) else {
+        return;
+    };
+    let (Some(sink_pad), Some(src_pad)) =
+        (first.bin.static_pad("sink"), last.bin.static_pad("src"))
+    else {
+        return;
+    };
+
+    let probe_id = src_pad.add_probe(PadProbeType::EVENT_DOWNSTREAM, |_pad, info| {
+        match info.event() {
+            Some(event) if event.type_() == gst::EventType::Eos => PadProbeReturn::Drop,
+            _ => PadProbeReturn::Ok,
+        }
+    });
+    sink_pad.send_event(gst::event::Eos::new());
+    if let Some(probe_id) = probe_id {
+        src_pad.remove_probe(probe_id);
+    }
+}
+
+fn describe(stages: &[FilterStage]) -> String {
+    std::iter::once("video_convert")
+        .chain(stages.iter().map(|stage| stage.name.as_str()))
+        .chain(std::iter::once("filter_tail"))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+    use crate::test_media;
+
+    const FILTERS: &[&str] = &["videoflip", "videobalance", "identity"];
+    const WAIT: Duration = Duration::from_secs(10);
+
+    // A live test pattern running through an empty chain
+    fn playing_chain() -> (gst::Pipeline, FilterChain) {
+        let pipeline = gst::parse::launch(
+            "videotestsrc is-live=true ! video/x-raw,width=160,height=120,framerate=60/1 \
+             ! videoconvert name=head ! identity name=tail ! fakesink",
+        )
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+        let chain = FilterChain::new(
+            pipeline.clone().upcast(),
+            pipeline.by_name("head").unwrap(),
+            pipeline.by_name("tail").unwrap(),
+        );
+        pipeline.set_state(gst::State::Playing).unwrap();
+        test_media::wait_for(&pipeline, gst::MessageType::AsyncDone).unwrap();
+        (pipeline, chain)
+    }
+
+    // Runs the test's main context until every change reported back
+    fn wait_for_changes(context: &glib::MainContext, outstanding: &Cell<usize>) {
+        let deadline = Instant::now() + WAIT;
+        while outstanding.get() > 0 {
+            assert!(
+                Instant::now() < deadline,
+                "{} changes never linked",
+                outstanding.get()
+            );
+            context.iteration(false);
+        }
+    }
+
+    fn run(test: impl FnOnce(&glib::MainContext)) {
+        test_media::init();
+        let context = glib::MainContext::new();
+        context.with_thread_default(|| test(&context)).unwrap();
+    }
+
+    #[test]
+    fn changes_made_before_the_probe_fires_are_all_linked() {
+        run(|context| {
+            let (pipeline, chain) = playing_chain();
+            let outstanding = Rc::new(Cell::new(0));
+            for name in FILTERS {
+                outstanding.set(outstanding.get() + 1);
+                let outstanding_clone = outstanding.clone();
+                chain
+                    .add(name, move |result| {
+                        result.unwrap();
+                        outstanding_clone.set(outstanding_clone.get() - 1);
+                    })
+                    .unwrap();
+            }
+            wait_for_changes(context, &outstanding);
+            pipeline.set_state(gst::State::Null).unwrap();
+
+            assert_eq!(chain.names(), FILTERS);
+        });
+    }
+
+    #[test]
+    fn done_is_called_right_away_when_nothing_flows() {
+        run(|_context| {
+            let (pipeline, chain) = playing_chain();
+            pipeline.set_state(gst::State::Null).unwrap();
+
+            let result = Rc::new(RefCell::new(None));
+            let result_clone = result.clone();
+            chain
+                .set(&["videoflip".to_string()], move |relinked| {
+                    result_clone.replace(Some(relinked));
+                })
+                .unwrap();
+            assert!(matches!(result.take(), Some(Ok(()))));
+            assert_eq!(chain.names(), ["videoflip"]);
+        });
+    }
+
+    #[test]
+    fn missing_filters_leave_the_chain_alone() {
+        run(|_context| {
+            let (pipeline, chain) = playing_chain();
+            pipeline.set_state(gst::State::Null).unwrap();
+
+            let called = Rc::new(Cell::new(false));
+            let called_clone = called.clone();
+            assert!(chain
+                .add("no-such-filter", move |_| called_clone.set(true))
+                .is_err());
+            assert!(!called.get());
+            assert!(chain.names().is_empty());
+        });
+    }
+
+    #[test]
+    fn hundreds_of_swaps_while_playing() {
+        run(|context| {
+            let (pipeline, chain) = playing_chain();
+            // What the chain should hold, changed the same way as the chain
+            let mut expected: Vec<String> = Vec::new();
+            let outstanding = Rc::new(Cell::new(0));
+            let failures = Rc::new(RefCell::new(Vec::new()));
+            let mut seed: u32 = 0x1234_5678;
+
+            for round in 0..300 {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let pick = (seed >> 16) as usize;
+                let outstanding_clone = outstanding.clone();
+                let failures_clone = failures.clone();
+                let done = move |result: Result<(), GstreamerError>| {
+                    if let Err(err) = result {
+                        failures_clone.borrow_mut().push(err.to_string());
+                    }
+                    outstanding_clone.set(outstanding_clone.get() - 1);
+                };
+                outstanding.set(outstanding.get() + 1);
+
+                match pick % 4 {
+                    0 | 1 if expected.len() < 4 => {
+                        let name = FILTERS[pick % FILTERS.len()];
+                        chain.add(name, done).unwrap();
+                        expected.push(name.to_string());
+                    }
+                    2 if expected.len() > 1 => {
+                        let (from, to) = (pick % expected.len(), (pick / 7) % expected.len());
+                        chain.move_stage(from, to, done).unwrap();
+                        let name = expected.remove(from);
+                        expected.insert(to, name);
+                    }
+                    _ if !expected.is_empty() => {
+                        let index = pick % expected.len();
+                        chain.remove(index, done).unwrap();
+                        expected.remove(index);
+                    }
+                    _ => {
+                        chain.clear(done).unwrap();
+                        expected.clear();
+                    }
+                }
+
+                // Mostly let changes pile up, sometimes let the probe catch up
+                if round % 5 == 0 {
+                    wait_for_changes(context, &outstanding);
+                    assert_eq!(chain.names(), expected, "round {round}");
+                }
+            }
+            wait_for_changes(context, &outstanding);
+
+            assert!(failures.borrow().is_empty(), "{:?}", failures.borrow());
+            assert_eq!(chain.names(), expected);
+            let bus = pipeline.bus().unwrap();
+            assert!(bus.pop_filtered(&[gst::MessageType::Error]).is_none());
+            assert_eq!(pipeline.state(gst::ClockTime::ZERO).1, gst::State::Playing);
+
+            // Frames still make it through the final chain
+            let frames = Arc::new(AtomicUsize::new(0));
+            let frames_clone = frames.clone();
+            pipeline
+                .by_name("tail")
+                .unwrap()
+                .static_pad("src")
+                .unwrap()
+                .add_probe(gst::PadProbeType::BUFFER, move |_, _| {
+                    frames_clone.fetch_add(1, Ordering::SeqCst);
+                    gst::PadProbeReturn::Ok
+                })
+                .unwrap();
+            let deadline = Instant::now() + WAIT;
+            while frames.load(Ordering::SeqCst) < 10 {
+                assert!(Instant::now() < deadline, "No frames after the last swap");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            pipeline.set_state(gst::State::Null).unwrap();
+        });
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk::{prelude::*, Button, Orientation};
+
+use crate::{
+    chains::ChainStore, error::GstreamerError, error_banner::ErrorBanner, filter_chain::RelinkDone,
+    filters::FilterRegistry, gstreamer::GstreamerManager, preset_bar::PresetBar,
+    property_editor::PropertyEditor,
+};
+
+// Sidebar with the active filter stack, presets and the saved chains
+#[derive(Clone)]
+pub struct FilterPanel {
+    container: gtk::Box,
+    registry: Rc<RefCell<FilterRegistry>>,
+    filter_list: gtk::StringList,
+    filter_selector: gtk::DropDown,
+    filter_description: gtk::Label,
+    add_button: Button,
+    stack_list: gtk::ListBox,
+    property_editor: PropertyEditor,
+    chain_list: gtk::StringList,
+    chain_selector: gtk::DropDown,
+    gst_manager: GstreamerManager,
+    chains: Rc<RefCell<Option<ChainStore>>>,
+    // Changes that are not linked yet, the stack rows are stale meanwhile
+    relinking: Rc<Cell<u32>>,
+    error_banner: ErrorBanner,
+}
+
+impl FilterPanel {
+    pub fn new(gst_manager: GstreamerManager, error_banner: ErrorBanner) -> Self {
+        let filter_list = gtk::StringList::new(&[]);
+        let filter_selector = gtk::DropDown::builder().model(&filter_list).build();
+        filter_selector.set_css_classes(&["filter-selector"]);
+        filter_selector.set_hexpand(true);
+        let add_button = Button::with_label("＋ Add");
+
+        let add_box = gtk::Box::new(Orientation::Horizontal, 5);
+        add_box.append(&filter_selector);
+        add_box.append(&add_button);
+
+        let filter_description = gtk::Label::new(None);
+        filter_description.set_halign(gtk::Align::Start);
+        filter_description.set_wrap(true);
+        filter_description.set_max_width_chars(40);
+        let discover_toggle = gtk::CheckButton::with_label("Show all installed effects");
+
+        let stack_list = gtk::ListBox::new();
+        stack_list.set_selection_mode(gtk::SelectionMode::Single);
+        let stack_scroll = gtk::ScrolledWindow::builder()
+            .child(&stack_list)
+            .min_content_width(240)
+            .min_content_height(120)
+            .build();
+        let clear_button = Button::with_label("Clear filters");
+
+        let property_editor = PropertyEditor::new(error_banner.clone());
+
+        let chain_list = gtk::StringList::new(&[]);
+        let chain_selector = gtk::DropDown::builder().model(&chain_list).build();
+        chain_selector.set_hexpand(true);
+        let load_chain_button = Button::with_label("Load");
+        let delete_chain_button = Button::with_label("Delete");
+
+        let chain_box = gtk::Box::new(Orientation::Horizontal, 5);
+        chain_box.append(&chain_selector);
+        chain_box.append(&load_chain_button);
+        chain_box.append(&delete_chain_button);
+
+        let chain_name_entry = gtk::Entry::new();
+        chain_name_entry.set_placeholder_text(Some("Chain name"));
+        chain_name_entry.set_hexpand(true);
+        let save_chain_button = Button::with_label("Save chain");
+
+        let save_box = gtk::Box::new(Orientation::Horizontal, 5);
+        save_box.append(&chain_name_entry);
+        save_box.append(&save_chain_button);
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.set_css_classes(&["filter-panel"]);
+        container.append(&add_box);
+        container.append(&filter_description);
+        container.append(&discover_toggle);
+        container.append(&stack_scroll);
+        container.append(&clear_button);
+        container.append(property_editor.widget());
+        container.append(&chain_box);
+        container.append(&save_box);
+
+        let chains = match ChainStore::load() {
+            Ok(chains) => Some(chains),
+            Err(err) => {
+                error_banner.show(&err);
+                chain_box.set_sensitive(false);
+                save_box.set_sensitive(false);
+                None
+            }
+        };
+
+        let panel = Self {
+            container,
+            registry: Rc::new(RefCell::new(FilterRegistry::known())),
+            filter_list,
+            filter_selector,
+            filter_description,
+            add_button: add_button.clone(),
+            stack_list,
+            property_editor,
+            chain_list,
+            chain_selector,
+            gst_manager,
+            chains: Rc::new(RefCell::new(chains)),
+            relinking: Rc::new(Cell::new(0)),
+            error_banner,
+        };
+        panel.refresh_filters();
+        panel.refresh_stack(None);
+        panel.refresh_chains();
+
+        let panel_clone = panel.clone();
+        let preset_bar = PresetBar::new(
+            panel.gst_manager.clone(),
+            panel.error_banner.clone(),
+            move |stages| {
+                panel_clone.apply(None, |gst_manager, done| {
+                    gst_manager.set_filter_stages(stages, done)
+                })
+            },
+        );
+        panel
+            .container
+            .insert_child_after(preset_bar.widget(), Some(&discover_toggle));
+
+        let panel_clone = panel.clone();
+        panel.filter_selector.connect_selected_notify(move |_| {
+            panel_clone.refresh_description();
+        });
+
+        let panel_clone = panel.clone();
+        discover_toggle.connect_toggled(move |toggle| {
+            panel_clone.registry.replace(if toggle.is_active() {
+                FilterRegistry::discover()
+            } else {
+                FilterRegistry::known()
+            });
+            panel_clone.refresh_filters();
+        });
+
+        let panel_clone = panel.clone();
+        panel.stack_list.connect_row_selected(move |_, row| {
+            let stage = row
+                .and_then(|row| usize::try_from(row.index()).ok())
+                .and_then(|index| panel_clone.gst_manager.filters().get(index).cloned());
+            panel_clone.property_editor.show(stage.as_ref());
+        });
+
+        let panel_clone = panel.clone();
+        add_button.connect_clicked(move |_| {
+            let Some(name) = panel_clone.selected_filter() else {
+                return;
+            };
+            let last_index = panel_clone.gst_manager.filter_names().len();
+            panel_clone.apply(Some(last_index), |gst_manager, done| {
+                gst_manager.add_filter(&name, done)
+            });
+        });
+
+        let panel_clone = panel.clone();
+        clear_button.connect_clicked(move |_| panel_clone.clear_filters());
+
+        let panel_clone = panel.clone();
+        load_chain_button.connect_clicked(move |_| {
+            let Some(name) = panel_clone.selected_chain() else {
+                return;
+            };
+            let filters = panel_clone
+                .chains
+                .borrow()
+                .as_ref()
+                .and_then(|chains| chains.get(&name).map(<[String]>::to_vec));
+            if let Some(filters) = filters {
+                panel_clone.apply(None, |gst_manager, done| {
+                    gst_manager.set_filters(&filters, done)
+                });
+            }
+        });
+
+        let panel_clone = panel.clone();
+        delete_chain_button.connect_clicked(move |_| {
+            let Some(name) = panel_clone.selected_chain() else {
+                return;
+            };
+            panel_clone.update_chains(|chains| chains.remove(&name));
+        });
+
+        let panel_clone = panel.clone();
+        save_chain_button.connect_clicked(move |_| {
+            let name = chain_name_entry.text().trim().to_string();
+            if name.is_empty() {
+                panel_clone
+                    .error_banner
+                    .show(&"Give the chain a name first");
+                return;
+            }
+            let filters = panel_clone.gst_manager.filter_names();
+            panel_clone.update_chains(|chains| chains.insert(&name, filters));
+            chain_name_entry.set_text("");
+        });
+
+        panel
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    pub fn clear_filters(&self) {
+        self.apply(None, |gst_manager, done| gst_manager.clear_filters(done));
+    }
+
+    fn selected_filter(&self) -> Option<String> {
+        self.registry
+            .borrow()
+            .filters()
+            .get(self.filter_selector.selected() as usize)
+            .map(|info| info.name.clone())
+    }
+
+    fn refresh_filters(&self) {
+        let registry = self.registry.borrow();
+        let display_names: Vec<String> = registry
+            .filters()
+            .iter()
+            .map(|info| format!("{} ({})", info.display_name, info.name))
+            .collect();
+        // The known effects that can't be offered
+        let missing = registry.missing();
+        self.filter_selector.set_tooltip_text(
+            (!missing.is_empty())
+                .then(|| format!("Not installed: {}", missing.join(", ")))
+                .as_deref(),
+        );
+        let display_names: Vec<&str> = display_names.iter().map(String::as_str).collect();
+        self.filter_list
+            .splice(0, self.filter_list.n_items(), &display_names);
+
+        self.add_button.set_sensitive(!display_names.is_empty());
+        self.refresh_description();
+    }
+
+    fn refresh_description(&self) {
+        let registry = self.registry.borrow();
+        match registry
+            .filters()
+            .get(self.filter_selector.selected() as usize)
+        {
+            Some(info) => self
+                .filter_description
+                .set_text(&format!("{} — {}", info.description, info.category)),
+            None => self
+                .filter_description
+                .set_text("None of the supported effects are installed"),
+        }
+    }
+
+    // Runs a change on the chain, then shows the relinked stack with the
+    // stage at `select` selected. The stack can't be used until then, its
+    // indices would be off.
+    fn apply(
+        &self,
+        select: Option<usize>,
+        change: impl FnOnce(&GstreamerManager, RelinkDone) -> Result<(), GstreamerError>,
+    ) {
+        self.relinking.set(self.relinking.get() + 1);
+        self.stack_list.set_sensitive(false);
+
+        let panel = self.clone();
+        let done: RelinkDone = Box::new(move |result| {
+            match result {
+                Ok(()) => panel.error_banner.hide(),
+                Err(err) => panel.error_banner.show(&err),
+            }
+            panel.finish_change(select);
+        });
+        if let Err(err) = change(&self.gst_manager, done) {
+            self.error_banner.show(&err);
+            self.finish_change(None);
+        }
+    }
+
+    fn finish_change(&self, select: Option<usize>) {
+        self.relinking.set(self.relinking.get().saturating_sub(1));
+        if self.relinking.get() == 0 {
+            self.stack_list.set_sensitive(true);
+            self.refresh_stack(select);
+        }
+    }
+
+    fn refresh_stack(&self, select: Option<usize>) {
+        self.stack_list.remove_all();
+        self.property_editor.show(None);
+
+        let filter_names = self.gst_manager.filter_names();
+        let last_index = filter_names.len().saturating_sub(1);
+        for (index, name) in filter_names.iter().enumerate() {
+            let name_label = gtk::Label::new(Some(name));
+            name_label.set_halign(gtk::Align::Start);
+            name_label.set_hexpand(true);
+
+            let up_button = Button::with_label("↑");
+            up_button.set_sensitive(index > 0);
+            let down_button = Button::with_label("↓");
+            down_button.set_sensitive(index < last_index);
+            let remove_button = Button::with_label("✕");
+
+            let row_box = gtk::Box::new(Orientation::Horizontal, 5);
+            row_box.append(&name_label);
+            row_box.append(&up_button);
+            row_box.append(&down_button);
+            row_box.append(&remove_button);
+            self.stack_list.append(&row_box);
+
+            let panel_clone = self.clone();
+            up_button.connect_clicked(move |_| {
+                panel_clone.apply(Some(index - 1), |gst_manager, done| {
+                    gst_manager.move_filter(index, index - 1, done)
+                });
+            });
+
+            let panel_clone = self.clone();
+            down_button.connect_clicked(move |_| {
+                panel_clone.apply(Some(index + 1), |gst_manager, done| {
+                    gst_manager.move_filter(index, index + 1, done)
+                });
+            });
+
+            let panel_clone = self.clone();
+            remove_button.connect_clicked(move |_| {
+                panel_clone.apply(None, |gst_manager, done| {
+                    gst_manager.remove_filter(index, done)
+                });
+            });
+        }
+
+        let row = select
+            .and_then(|index| i32::try_from(index).ok())
+            .and_then(|index| self.stack_list.row_at_index(index));
+        if let Some(row) = row {
+            self.stack_list.select_row(Some(&row));
+        }
+    }
+
+    fn selected_chain(&self) -> Option<String> {
+        self.chain_list
+            .string(self.chain_selector.selected())
+            .map(|name| name.to_string())
+    }
+
+    fn update_chains(&self, change: impl FnOnce(&mut ChainStore) -> Result<(), GstreamerError>) {
+        let result = match self.chains.borrow_mut().as_mut() {
+            Some(chains) => change(chains),
+            None => return,
+        };
+        if let Err(err) = result {
+            self.error_banner.show(&err);
+        }
+        self.refresh_chains();
+    }
+
+    fn refresh_chains(&self) {
+        let chains = self.chains.borrow();
+        let names = chains.as_ref().map(ChainStore::names).unwrap_or_default();
+        self.chain_list.splice(0, self.chain_list.n_items(), &names);
+    }
+}
@@ -1,9 +1,20 @@
+use gst::{prelude::*, Element};
+
+use crate::{
+    error::GstreamerError,
+    gstreamer::{add_ghost_pad, make_element},
+    LOG_DOMAIN,
+};
+
+const VIDEO_CONVERT: &str = "videoconvert";
+
 pub const NO_FILTER: &str = "no-filter";
-pub const FILTER_NAMES: &[&str] = &[
-    NO_FILTER,
+// Effects the app was written around, listed first when installed
+const KNOWN_FILTERS: &[&str] = &[
     "timeoverlay",
     "vertigotv",
     "edgetv",
+    "dicetv",
     "agingtv",
     "shagadelictv",
     "revtv",
@@ -11,4 +22,209 @@ pub const FILTER_NAMES: &[&str] = &[
     "twirl",
     "kaleidoscope",
     "tunnel",
+    "glfilterblur",
+    "glfiltersobel",
+    "gleffects_sepia",
+    "gleffects_heat",
 ];
+// Inherited from GstObject / GstBaseTransform, not interesting to tweak
+const SKIPPED_PROPERTIES: &[&str] = &["name", "parent", "qos"];
+const EFFECT_CATEGORY: &str = "Filter/Effect/Video";
+const GL_MEMORY: &str = "memory:GLMemory";
+const GL_UPLOAD: &str = "glupload";
+const GL_COLOR_CONVERT: &str = "glcolorconvert";
+const GL_DOWNLOAD: &str = "gldownload";
+
+#[derive(Debug, Clone)]
+pub struct FilterInfo {
+    pub name: String,
+    pub display_name: String,
+    pub description: String,
+    pub category: String,
+}
+
+impl FilterInfo {
+    fn from_factory(factory: &gst::ElementFactory) -> Self {
+        Self {
+            name: factory.name().to_string(),
+            display_name: factory.longname().to_string(),
+            description: factory.description().to_string(),
+            category: factory.klass().to_string(),
+        }
+    }
+}
+
+// The filters that are actually installed, looked up in the GStreamer registry
+pub struct FilterRegistry {
+    filters: Vec<FilterInfo>,
+    // Known effects that aren't installed
+    missing: Vec<String>,
+}
+
+impl FilterRegistry {
+    // Only the known effects that exist on this system
+    pub fn known() -> Self {
+        Self::with_names(KNOWN_FILTERS)
+    }
+
+    fn with_names(names: &[&str]) -> Self {
+        let mut filters = Vec::new();
+        let mut missing = Vec::new();
+        for &name in names {
+            match gst::ElementFactory::find(name) {
+                Some(factory) => filters.push(FilterInfo::from_factory(&factory)),
+                None => missing.push(name.to_string()),
+            }
+        }
+        if !missing.is_empty() {
+            glib::g_info!(LOG_DOMAIN, "Filters not installed: {}", missing.join(", "));
+        }
+        Self { filters, missing }
+    }
+
+    // The known effects plus every other video effect in the registry
+    pub fn discover() -> Self {
+        let mut registry = Self::known();
+
+        let mut discovered: Vec<FilterInfo> = gst::Registry::get()
+            .features(gst::ElementFactory::static_type())
+            .into_iter()
+            .filter_map(|feature| feature.downcast::<gst::ElementFactory>().ok())
+            .filter(|factory| factory.klass().contains(EFFECT_CATEGORY))
+            .map(|factory| FilterInfo::from_factory(&factory))
+            .filter(|info| registry.get(&info.name).is_none())
+            .collect();
+        discovered.sort_by(|a, b| a.name.cmp(&b.name));
+
+        registry.filters.extend(discovered);
+        registry
+    }
+
+    pub fn filters(&self) -> &[FilterInfo] {
+        &self.filters
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FilterInfo> {
+        self.filters.iter().find(|info| info.name == name)
+    }
+
+    pub fn missing(&self) -> &[String] {
+        &self.missing
+    }
+}
+
+// An effect in the filter stack, behind its own videoconvert so any two
+// effects can follow each other regardless of the formats they accept.
+// OpenGL effects are wrapped in glupload/gldownload as well.
+#[derive(Debug, Clone)]
+pub struct FilterStage {
+    pub name: String,
+    pub element: Element,
+    pub bin: gst::Bin,
+}
+
+impl FilterStage {
+    pub fn new(name: &str) -> Result<Self, GstreamerError> {
+        let element = make_element(name)?;
+        let mut elements = vec![make_element(VIDEO_CONVERT)?];
+        if needs_gl_memory(&element) {
+            elements.push(make_element(GL_UPLOAD)?);
+            elements.push(make_element(GL_COLOR_CONVERT)?);
+            elements.push(element.clone());
+            elements.push(make_element(GL_DOWNLOAD)?);
+        } else {
+            elements.push(element.clone());
+        }
+
+        let bin = gst::Bin::new();
+        bin.add_many(&elements)?;
+        Element::link_many(&elements)
+            .map_err(|_| GstreamerError::Link(format!("videoconvert → {name}")))?;
+        if let (Some(first), Some(last)) = (elements.first(), elements.last()) {
+            add_ghost_pad(&bin, first, "sink")?;
+            add_ghost_pad(&bin, last, "src")?;
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            element,
+            bin,
+        })
+    }
+}
+
+impl PartialEq for FilterStage {
+    fn eq(&self, other: &Self) -> bool {
+        self.bin == other.bin
+    }
+}
+
+pub fn editable_properties(element: &Element) -> Vec<glib::ParamSpec> {
+    element
+        .list_properties()
+        .iter()
+        .filter(|pspec| {
+            let flags = pspec.flags();
+            flags.contains(glib::ParamFlags::READWRITE)
+                && !flags.contains(glib::ParamFlags::CONSTRUCT_ONLY)
+                && !SKIPPED_PROPERTIES.contains(&pspec.name())
+        })
+        .cloned()
+        .collect()
+}
+
+fn needs_gl_memory(element: &Element) -> bool {
+    element.factory().is_some_and(|factory| {
+        factory
+            .static_pad_templates()
+            .iter()
+            .filter(|template| template.direction() == gst::PadDirection::Sink)
+            .any(|template| {
+                template
+                    .caps()
+                    .iter_with_features()
+                    .any(|(_, features)| features.contains(GL_MEMORY))
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_media;
+
+    #[test]
+    fn installed_filters_are_found_and_missing_ones_reported() {
+        test_media::init();
+        let registry = FilterRegistry::with_names(&["videoflip", "no-such-effect", "videobalance"]);
+
+        let names: Vec<&str> = registry
+            .filters()
+            .iter()
+            .map(|info| info.name.as_str())
+            .collect();
+        assert_eq!(names, ["videoflip", "videobalance"]);
+        assert_eq!(registry.missing(), ["no-such-effect"]);
+        let info = registry.get("videoflip").unwrap();
+        assert!(!info.display_name.is_empty());
+        assert!(info.category.contains("Filter"));
+        assert!(registry.get("no-such-effect").is_none());
+    }
+
+    #[test]
+    fn discovery_adds_installed_effects_after_the_known_ones() {
+        test_media::init();
+        let known = FilterRegistry::known();
+        let discovered = FilterRegistry::discover();
+
+        let known_count = known.filters().len();
+        assert!(discovered.filters().len() >= known_count);
+        for (known, discovered) in known.filters().iter().zip(discovered.filters()) {
+            assert_eq!(known.name, discovered.name);
+        }
+        for info in &discovered.filters()[known_count..] {
+            assert!(info.category.contains(EFFECT_CATEGORY), "{}", info.name);
+        }
+        assert_eq!(known.missing(), discovered.missing());
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use gtk::{prelude::*, ApplicationWindow};
+
+use crate::window::add_action;
+
+// Fullscreen controls fade out after this long without mouse movement
+const CONTROLS_HIDE_DELAY: Duration = Duration::from_secs(3);
+
+// Moves the slider, the controls and the filter panel over the video while
+// fullscreen, and back into the window layout afterwards
+#[derive(Clone)]
+pub struct FullscreenUi {
+    window: ApplicationWindow,
+    main_box: gtk::Box,
+    content_box: gtk::Box,
+    overlay: gtk::Overlay,
+    overlay_controls: gtk::Box,
+    playlist_box: gtk::Box,
+    slider_box: gtk::Box,
+    controls_box: gtk::Box,
+    filter_panel: gtk::Box,
+    playlist_visible: Rc<Cell<bool>>,
+    hovering: Rc<Cell<bool>>,
+    hide_timeout: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl FullscreenUi {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        window: &ApplicationWindow,
+        main_box: gtk::Box,
+        content_box: gtk::Box,
+        overlay: gtk::Overlay,
+        playlist_box: gtk::Box,
+        slider_box: gtk::Box,
+        controls_box: gtk::Box,
+        filter_panel: gtk::Box,
+    ) -> Self {
+        let fullscreen_ui = Self {
+            window: window.clone(),
+            main_box,
+            content_box,
+            overlay,
+            overlay_controls: gtk::Box::new(gtk::Orientation::Vertical, 0),
+            playlist_box,
+            slider_box,
+            controls_box,
+            filter_panel,
+            playlist_visible: Rc::new(Cell::new(false)),
+            hovering: Rc::new(Cell::new(false)),
+            hide_timeout: Rc::new(RefCell::new(None)),
+        };
+
+        let window = fullscreen_ui.window.clone();
+        let overlay = fullscreen_ui.overlay.clone();
+
+        fullscreen_ui
+            .overlay_controls
+            .set_css_classes(&["overlay-controls"]);
+        fullscreen_ui.overlay_controls.set_valign(gtk::Align::End);
+        fullscreen_ui.overlay_controls.set_visible(false);
+        overlay.add_overlay(&fullscreen_ui.overlay_controls);
+
+        let window_clone = window.clone();
+        add_action(&window, "fullscreen", move || {
+            window_clone.set_fullscreened(!window_clone.is_fullscreen());
+        });
+
+        let window_clone = window.clone();
+        add_action(&window, "leave-fullscreen", move || {
+            window_clone.unfullscreen()
+        });
+
+        // Also covers the window manager's own fullscreen toggle
+        let fullscreen_ui_clone = fullscreen_ui.clone();
+        window.connect_fullscreened_notify(move |window| {
+            if window.is_fullscreen() {
+                fullscreen_ui_clone.enter();
+            } else {
+                fullscreen_ui_clone.leave();
+            }
+        });
+
+        let double_click = gtk::GestureClick::new();
+        let window_clone = window.clone();
+        double_click.connect_pressed(move |_, n_press, _, _| {
+            if n_press == 2 {
+                window_clone.set_fullscreened(!window_clone.is_fullscreen());
+            }
+        });
+        fullscreen_ui.picture_area().add_controller(double_click);
+
+        let motion_controller = gtk::EventControllerMotion::new();
+        let fullscreen_ui_clone = fullscreen_ui.clone();
+        motion_controller.connect_motion(move |_, _, _| fullscreen_ui_clone.reveal());
+        overlay.add_controller(motion_controller);
+
+        // Controls stay up while the pointer is on them
+        for controls in [&fullscreen_ui.overlay_controls, &fullscreen_ui.filter_panel] {
+            let hover_controller = gtk::EventControllerMotion::new();
+            let hovering_clone = fullscreen_ui.hovering.clone();
+            hover_controller.connect_enter(move |_, _, _| hovering_clone.set(true));
+            let hovering_clone = fullscreen_ui.hovering.clone();
+            hover_controller.connect_leave(move |_| hovering_clone.set(false));
+            controls.add_controller(hover_controller);
+        }
+
+        fullscreen_ui
+    }
+
+    fn picture_area(&self) -> gtk::Widget {
+        self.overlay
+            .child()
+            .unwrap_or_else(|| self.overlay.clone().upcast())
+    }
+
+    fn enter(&self) {
+        self.window.add_css_class("fullscreen-video");
+        self.playlist_visible.set(self.playlist_box.is_visible());
+        self.playlist_box.set_visible(false);
+
+        self.main_box.remove(&self.slider_box);
+        self.main_box.remove(&self.controls_box);
+        self.overlay_controls.append(&self.slider_box);
+        self.overlay_controls.append(&self.controls_box);
+        self.overlay_controls.set_visible(true);
+
+        self.content_box.remove(&self.filter_panel);
+        self.filter_panel.add_css_class("overlay-panel");
+        self.filter_panel.set_halign(gtk::Align::Start);
+        self.filter_panel.set_valign(gtk::Align::Start);
+        self.overlay.add_overlay(&self.filter_panel);
+
+        self.reveal();
+    }
+
+    fn leave(&self) {
+        self.cancel_hide();
+        self.window.remove_css_class("fullscreen-video");
+        self.set_controls_shown(true);
+        self.playlist_box.set_visible(self.playlist_visible.get());
+
+        self.overlay_controls.remove(&self.slider_box);
+        self.overlay_controls.remove(&self.controls_box);
+        self.overlay_controls.set_visible(false);
+        self.main_box.append(&self.slider_box);
+        self.main_box.append(&self.controls_box);
+
+        self.overlay.remove_overlay(&self.filter_panel);
+        self.filter_panel.remove_css_class("overlay-panel");
+        self.filter_panel.set_halign(gtk::Align::Fill);
+        self.filter_panel.set_valign(gtk::Align::Fill);
+        self.content_box.prepend(&self.filter_panel);
+    }
+
+    // Shows the controls and restarts the countdown to hiding them
+    fn reveal(&self) {
+        if !self.window.is_fullscreen() {
+            return;
+        }
+        self.set_controls_shown(true);
+        self.cancel_hide();
+
+        let fullscreen_ui = self.clone();
+        let source_id = glib::timeout_add_local_once(CONTROLS_HIDE_DELAY, move || {
+            fullscreen_ui.hide_timeout.replace(None);
+            if fullscreen_ui.hovering.get() {
+                fullscreen_ui.reveal();
+            } else if fullscreen_ui.window.is_fullscreen() {
+                fullscreen_ui.set_controls_shown(false);
+            }
+        });
+        self.hide_timeout.replace(Some(source_id));
+    }
+
+    fn cancel_hide(&self) {
+        if let Some(source_id) = self.hide_timeout.take() {
+            source_id.remove();
+        }
+    }
+
+    // Hidden controls fade out through CSS and stop taking clicks
+    fn set_controls_shown(&self, shown: bool) {
+        for controls in [&self.overlay_controls, &self.filter_panel] {
+            if shown {
+                controls.remove_css_class("faded");
+            } else {
+                controls.add_css_class("faded");
+            }
+            controls.set_can_target(shown);
+        }
+        self.overlay
+            .set_cursor_from_name(if shown { None } else { Some("none") });
+    }
+}
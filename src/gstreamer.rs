@@ -1,181 +1,1349 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
 use gst::{prelude::*, Element, ElementFactory, Pipeline};
+use gstreamer_audio::{prelude::*, StreamVolume, StreamVolumeFormat};
+
+use crate::{
+    bus::{BusWatcher, PlayerEvent},
+    encoding::{EncodingBranch, EncodingOptions},
+    error::GstreamerError,
+    filter_chain::FilterChain,
+    filters::FilterStage,
+    recording::Recording,
+    snapshot::Frame,
+    source::{self, local_path, make_source, to_uri, SourceKind},
+    subtitles::{find_sidecar, ExternalSubtitles, SubtitlePosition, SubtitleStyle},
+    tracks::{StreamSelection, TrackKind, TrackSelectors},
+    transform::{AspectRatio, Rotation, VideoTransform},
+    LOG_DOMAIN,
+};
 
-const SRC: &str = "filesrc";
 const DECODE: &str = "decodebin";
+const PLAYBIN: &str = "playbin3";
 const VIDEO_CONVERT: &str = "videoconvert";
 const VIDEO_SINK: &str = "gtk4paintablesink";
+const FILTER_TAIL: &str = "identity";
+const TEE: &str = "tee";
+const QUEUE: &str = "queue";
 const AUDIO_CONVERT: &str = "audioconvert";
 const AUDIO_SINK: &str = "autoaudiosink";
 const VOLUME: &str = "volume";
+const SCALETEMPO: &str = "scaletempo";
+// How long a restart for a new subtitle file may take to preroll
+const PLAYBIN_RESTART_TIMEOUT: gst::ClockTime = gst::ClockTime::from_seconds(5);
+
+pub const BACKEND_NAMES: &[&str] = &["decodebin", "playbin"];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Decodebin,
+    Playbin,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "decodebin" => Ok(Backend::Decodebin),
+            "playbin" | "playbin3" => Ok(Backend::Playbin),
+            _ => Err(format!(
+                "Unknown backend '{name}', expected one of: {}",
+                BACKEND_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct DecodebinElements {
+    filter_tail: Element,
+    // Audio and subtitle selectors plus the overlay drawing the subtitles
+    selectors: TrackSelectors,
+    // Subtitle file drawn over the embedded subtitles
+    external_subtitles: ExternalSubtitles,
+    // Tees after the subtitles and after the volume feed playback and recording
+    video_tee: Element,
+    video_queue: Element,
+    audio_convert: Element,
+    // Keeps the pitch of the audio when playing faster or slower
+    scaletempo: Element,
+    audio_tee: Element,
+    audio_queue: Element,
+    audio_sink: Element,
+    // Source plus the decodebins feeding the converters, replaced on every new URI
+    sources: Arc<Mutex<Vec<Element>>>,
+}
 
 #[derive(Clone)]
 pub struct GstreamerManager {
+    pub backend: Backend,
     pub pipeline: Pipeline,
-    pub src: Element,
-    pub decode: Element,
     pub vide_convert: Element,
     pub video_sink: Element,
-    pub audio_convert: Element,
-    pub audio_sink: Element,
+    // The `volume` element, or playbin3 itself which handles volume natively
     pub volume: Element,
-    pub filter: Option<Element>,
+    pub bus: BusWatcher,
+    // Filters live between `vide_convert` and an identity, inside the
+    // pipeline (decodebin) or the video-filter-bin (playbin)
+    filter_chain: FilterChain,
+    // Rotation, crop and aspect ratio, right after the filters
+    transform: VideoTransform,
+    decodebin: Option<DecodebinElements>,
+    // Audio and subtitle streams playbin3 announced
+    streams: Option<StreamSelection>,
+    // Picked up by playbin3's about-to-finish for gapless playback
+    next_uri: Arc<Mutex<Option<String>>>,
+    recording: Arc<Mutex<Option<Recording>>>,
+    // Playback rate kept by every seek, negative plays backwards
+    rate: Arc<Mutex<f64>>,
+    // A and B markers, while set every seek stays inside them as a segment
+    ab_loop: Arc<Mutex<Option<(gst::ClockTime, gst::ClockTime)>>>,
+    subtitle_style: Arc<Mutex<SubtitleStyle>>,
 }
 
 impl GstreamerManager {
-    pub fn new() -> Self {
-        Self {
-            pipeline: Pipeline::new(),
-            src: ElementFactory::make(SRC)
-                .build()
-                .unwrap_or_else(|_| panic!("Could not create {}", SRC)),
-            decode: ElementFactory::make(DECODE)
-                .build()
-                .unwrap_or_else(|_| panic!("Could not create {}", DECODE)),
-            vide_convert: ElementFactory::make(VIDEO_CONVERT)
-                .build()
-                .unwrap_or_else(|_| panic!("Could not create {}", VIDEO_CONVERT)),
-            video_sink: ElementFactory::make(VIDEO_SINK)
-                .build()
-                .unwrap_or_else(|_| panic!("Could not create {}", VIDEO_SINK)),
-            audio_convert: ElementFactory::make(AUDIO_CONVERT)
-                .build()
-                .unwrap_or_else(|_| panic!("Could not create {}", AUDIO_CONVERT)),
-            audio_sink: ElementFactory::make(AUDIO_SINK)
-                .build()
-                .unwrap_or_else(|_| panic!("Could not create {}", AUDIO_SINK)),
-            volume: ElementFactory::make(VOLUME)
-                .build()
-                .unwrap_or_else(|_| panic!("Could not create {}", VOLUME)),
-            filter: None,
-        }
-    }
-
-    pub fn create_pipeline(&mut self, video_path: &str) {
-        self.src.set_property("location", video_path);
+    pub fn new(backend: Backend) -> Result<Self, GstreamerError> {
+        match backend {
+            Backend::Decodebin => Self::new_decodebin(),
+            Backend::Playbin => Self::new_playbin(),
+        }
+    }
 
-        self.pipeline
-            .add_many([
-                &self.src,
-                &self.decode,
-                &self.vide_convert,
+    fn new_decodebin() -> Result<Self, GstreamerError> {
+        let pipeline = Pipeline::new();
+        let bus = BusWatcher::new(&pipeline)?;
+        let video_sink = make_element(VIDEO_SINK)?;
+        let audio_sink = make_element(AUDIO_SINK)?;
+
+        Self::with_decodebin_sinks(pipeline, bus, video_sink, audio_sink)
+    }
+
+    // Same graph as the decodebin backend, but the sinks are swapped for an
+    // encoder + muxer + filesink branch writing to `location`
+    pub fn new_render(options: &EncodingOptions, location: &str) -> Result<Self, GstreamerError> {
+        let pipeline = Pipeline::new();
+        let bus = BusWatcher::new(&pipeline)?;
+        let branch = EncodingBranch::new(pipeline.upcast_ref(), options, location)?;
+
+        Self::with_decodebin_sinks(pipeline, bus, branch.video_entry, branch.audio_entry)
+    }
+
+    fn with_decodebin_sinks(
+        pipeline: Pipeline,
+        bus: BusWatcher,
+        video_sink: Element,
+        audio_sink: Element,
+    ) -> Result<Self, GstreamerError> {
+        let video_tee = make_element(TEE)?;
+        let audio_tee = make_element(TEE)?;
+        for tee in [&video_tee, &audio_tee] {
+            set_element_property(tee, "allow-not-linked", true)?;
+        }
+
+        let vide_convert = make_element(VIDEO_CONVERT)?;
+        let filter_tail = make_element(FILTER_TAIL)?;
+        let filter_chain = FilterChain::new(
+            pipeline.clone().upcast(),
+            vide_convert.clone(),
+            filter_tail.clone(),
+        );
+        let selectors = TrackSelectors::new(&pipeline)?;
+
+        Ok(Self {
+            backend: Backend::Decodebin,
+            filter_chain,
+            transform: VideoTransform::new()?,
+            pipeline,
+            vide_convert,
+            video_sink,
+            volume: make_element(VOLUME)?,
+            bus,
+            decodebin: Some(DecodebinElements {
+                filter_tail,
+                selectors,
+                external_subtitles: ExternalSubtitles::new()?,
+                video_tee,
+                video_queue: make_element(QUEUE)?,
+                audio_convert: make_element(AUDIO_CONVERT)?,
+                scaletempo: make_element(SCALETEMPO)?,
+                audio_tee,
+                audio_queue: make_element(QUEUE)?,
+                audio_sink,
+                sources: Arc::new(Mutex::new(Vec::new())),
+            }),
+            streams: None,
+            next_uri: Arc::new(Mutex::new(None)),
+            recording: Arc::new(Mutex::new(None)),
+            rate: Arc::new(Mutex::new(1.0)),
+            ab_loop: Arc::new(Mutex::new(None)),
+            subtitle_style: Arc::new(Mutex::new(SubtitleStyle::default())),
+        })
+    }
+
+    fn new_playbin() -> Result<Self, GstreamerError> {
+        let playbin = make_element(PLAYBIN)?;
+        let pipeline = playbin
+            .clone()
+            .downcast::<Pipeline>()
+            .map_err(|_| glib::bool_error!("{PLAYBIN} is not a pipeline"))?;
+        let bus = BusWatcher::new(&pipeline)?;
+
+        let vide_convert = make_element(VIDEO_CONVERT)?;
+        let filter_tail = make_element(FILTER_TAIL)?;
+        let video_sink = make_element(VIDEO_SINK)?;
+
+        let transform = VideoTransform::new()?;
+
+        // playbin3 only takes a single element as video filter, so the filter
+        // chain and the transform are wrapped in a bin exposing the ends as
+        // ghost pads
+        let filter_bin = gst::Bin::with_name("video-filter-bin");
+        let transform_bin = transform.bin.upcast_ref::<Element>();
+        filter_bin.add_many([&vide_convert, &filter_tail, transform_bin])?;
+        Element::link_many([&vide_convert, &filter_tail, transform_bin]).map_err(|_| {
+            GstreamerError::Link("video_convert → filter_tail → transform".to_string())
+        })?;
+        add_ghost_pad(&filter_bin, &vide_convert, "sink")?;
+        add_ghost_pad(&filter_bin, transform_bin, "src")?;
+
+        set_element_property(&playbin, "video-filter", &filter_bin)?;
+        set_element_property(&playbin, "video-sink", &video_sink)?;
+        set_element_property(&playbin, "audio-filter", &make_element(SCALETEMPO)?)?;
+
+        let streams = StreamSelection::default();
+        let streams_clone = streams.clone();
+        bus.subscribe(move |event| match event {
+            PlayerEvent::StreamCollection(collection) => streams_clone.set_collection(collection),
+            PlayerEvent::StreamsSelected(selected) => streams_clone.set_selected(selected),
+            _ => {}
+        });
+
+        let next_uri: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let next_uri_clone = next_uri.clone();
+        playbin.connect("about-to-finish", false, move |args| {
+            let Ok(playbin) = args[0].get::<Element>() else {
+                return None;
+            };
+            if let Some(uri) = next_uri_clone.lock().ok().and_then(|mut uri| uri.take()) {
+                glib::g_info!(LOG_DOMAIN, "Queueing '{uri}' for gapless playback");
+                let suburi = find_sidecar(&uri)
+                    .and_then(|path| glib::filename_to_uri(path, None).ok())
+                    .map(|suburi| suburi.to_string());
+                playbin.set_property("suburi", suburi);
+                playbin.set_property("uri", uri);
+            }
+            None
+        });
+
+        Ok(Self {
+            backend: Backend::Playbin,
+            pipeline,
+            vide_convert: vide_convert.clone(),
+            video_sink,
+            volume: playbin,
+            bus,
+            filter_chain: FilterChain::new(filter_bin, vide_convert, filter_tail),
+            transform,
+            decodebin: None,
+            streams: Some(streams),
+            next_uri,
+            recording: Arc::new(Mutex::new(None)),
+            rate: Arc::new(Mutex::new(1.0)),
+            ab_loop: Arc::new(Mutex::new(None)),
+            subtitle_style: Arc::new(Mutex::new(SubtitleStyle::default())),
+        })
+    }
+
+    pub fn create_pipeline(&mut self, uri: &str) -> Result<(), GstreamerError> {
+        let uri = to_uri(uri)?;
+        let Some(elements) = self.decodebin.clone() else {
+            set_element_property(self.pipeline.upcast_ref(), "uri", uri.as_str())?;
+            return self.load_subtitles(find_sidecar(&uri).as_deref());
+        };
+
+        for element in [
+            &self.vide_convert,
+            &elements.filter_tail,
+            &elements.video_tee,
+            &elements.video_queue,
+            &self.video_sink,
+            &elements.audio_convert,
+            &elements.scaletempo,
+            &self.volume,
+            &elements.audio_tee,
+            &elements.audio_queue,
+            &elements.audio_sink,
+        ]
+        .into_iter()
+        .chain(elements.selectors.elements())
+        .chain([self.transform.bin.upcast_ref()])
+        .chain(elements.external_subtitles.overlays())
+        {
+            // Encoding branches used as sinks are already inside the pipeline
+            if element.parent().is_none() {
+                self.pipeline.add(element)?;
+            }
+        }
+
+        let subtitle_overlay = &elements.selectors.subtitle_overlay;
+        Element::link_many([
+            &self.vide_convert,
+            &elements.filter_tail,
+            self.transform.bin.upcast_ref(),
+        ])
+        .and_then(|_| {
+            Element::link_pads(
+                self.transform.bin.upcast_ref(),
+                Some("src"),
+                subtitle_overlay,
+                Some("video_sink"),
+            )
+        })
+        .and_then(|_| {
+            let mut upstream = subtitle_overlay;
+            for overlay in elements.external_subtitles.overlays() {
+                Element::link_pads(upstream, Some("src"), overlay, Some("video_sink"))?;
+                upstream = overlay;
+            }
+            Element::link_many([
+                upstream,
+                &elements.video_tee,
+                &elements.video_queue,
                 &self.video_sink,
-                &self.audio_convert,
-                &self.audio_sink,
-                &self.volume,
             ])
-            .expect("Failed to add elements");
+        })
+        .map_err(|_| {
+            GstreamerError::Link(
+                "video_convert → filter_tail → transform → subtitles → subtitle file \
+                 → tee → video_sink"
+                    .to_string(),
+            )
+        })?;
+        Element::link_many([
+            &elements.selectors.audio_selector,
+            &elements.audio_convert,
+            &elements.scaletempo,
+            &self.volume,
+            &elements.audio_tee,
+            &elements.audio_queue,
+            &elements.audio_sink,
+        ])
+        .map_err(|_| {
+            GstreamerError::Link(
+                "audio_selector → audio_convert → scaletempo → volume → tee → audio_sink"
+                    .to_string(),
+            )
+        })?;
 
-        Element::link_many([&self.src, &self.decode]).expect("Link src → decode failed");
-        Element::link_many([&self.vide_convert, &self.video_sink])
-            .expect("Link video_convert → video_sink failed");
-        Element::link_many([&self.audio_convert, &self.volume, &self.audio_sink])
-            .expect("Link audio_convert → Link volume → audio_sink failed");
+        self.add_source(&uri, &elements)?;
+        self.load_subtitles(find_sidecar(&uri).as_deref())
+    }
 
-        let video_convert_clone = self.vide_convert.clone();
-        let audio_convert_clone = self.audio_convert.clone();
-        self.decode.connect_pad_added(move |_dbin, src_pad| {
-            let Some(caps) = src_pad.current_caps() else {
-                eprintln!("Failed to get caps for pad");
-                return;
-            };
-            let Some(structure) = caps.structure(0) else {
-                eprintln!("Failed to get structure for caps");
-                return;
-            };
+    pub fn start_recording(
+        &self,
+        options: &EncodingOptions,
+        location: &str,
+    ) -> Result<(), GstreamerError> {
+        let Some(elements) = &self.decodebin else {
+            return Err(GstreamerError::Unsupported(
+                "Recording needs the decodebin backend".to_string(),
+            ));
+        };
 
-            let name = structure.name();
+        let mut recording = self.recording.lock().unwrap();
+        match recording.as_ref() {
+            Some(recording) if recording.is_stopping() => {
+                return Err(GstreamerError::Unsupported(
+                    "Recording while the last recording is still being saved".to_string(),
+                ));
+            }
+            Some(recording) if !recording.has_ended() => return Ok(()),
+            _ => {}
+        }
 
-            if name.starts_with("video/") {
-                let sink_pad = video_convert_clone
-                    .static_pad("sink")
-                    .expect("Failed to get sink pad from videoconvert");
+        let has_audio = elements.selectors.has_audio();
+        *recording = Some(Recording::start(
+            &self.pipeline,
+            &elements.video_tee,
+            &elements.audio_tee,
+            has_audio,
+            options,
+            location,
+        )?);
+        Ok(())
+    }
 
-                if sink_pad.is_linked() {
-                    return;
-                }
+    // The recording stays around until its file is complete, so seeks are
+    // refused until then
+    pub fn stop_recording(&self) {
+        if let Some(recording) = self.recording.lock().unwrap().as_mut() {
+            recording.stop();
+        }
+    }
 
-                if let Err(err) = src_pad.link(&sink_pad) {
-                    eprintln!("Pad link failed: {err:?}");
-                }
-            } else if name.starts_with("audio/") {
-                let sink_pad = audio_convert_clone
-                    .static_pad("sink")
-                    .expect("Failed to get sink pad from audioconvert");
+    // False as soon as the recording is stopped, even while it is being saved
+    pub fn is_recording(&self) -> bool {
+        self.recording
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|recording| !recording.is_stopping() && !recording.has_ended())
+    }
 
-                if sink_pad.is_linked() {
-                    return;
-                }
+    // Leaves the pipeline in READY, the caller decides when to start playing
+    pub fn set_uri(&self, uri: &str) -> Result<(), GstreamerError> {
+        let uri = to_uri(uri)?;
+        // Markers belong to the previous file
+        *self.ab_loop.lock().unwrap() = None;
+        self.pipeline
+            .set_state(gst::State::Ready)
+            .map_err(|_| GstreamerError::StateChange {
+                element: self.pipeline.name().to_string(),
+                state: gst::State::Ready,
+            })?;
 
-                if let Err(err) = src_pad.link(&sink_pad) {
-                    eprintln!("Pad link failed: {err:?}");
-                }
+        let Some(elements) = &self.decodebin else {
+            set_element_property(self.pipeline.upcast_ref(), "uri", uri.as_str())?;
+            return self.load_subtitles(find_sidecar(&uri).as_deref());
+        };
+
+        // Everything of the previous file goes, even when one element fails to,
+        // so the pipeline never ends up with half a source
+        let old_sources = std::mem::take(&mut *elements.sources.lock().unwrap());
+        for old_source in &old_sources {
+            if old_source.set_state(gst::State::Null).is_err() {
+                glib::g_warning!(LOG_DOMAIN, "Failed to stop {}", old_source.name());
             }
-        });
+        }
+        for old_source in &old_sources {
+            if let Err(err) = self.pipeline.remove(old_source) {
+                glib::g_warning!(LOG_DOMAIN, "Failed to remove {}: {err}", old_source.name());
+            }
+        }
+        elements.selectors.reset();
+
+        self.add_source(&uri, elements)?;
+        self.load_subtitles(find_sidecar(&uri).as_deref())
     }
 
-    pub fn remove_filer_and_continue_pipeline(&mut self) {
-        if let Some(old_filter) = self.filter.take() {
-            old_filter
-                .set_state(gst::State::Null)
-                .expect("Failed to set old filter to NULL");
+    pub fn set_next_uri(&self, uri: Option<&str>) {
+        *self.next_uri.lock().unwrap() = uri.map(str::to_string);
+    }
 
-            self.pipeline
-                .remove(&old_filter)
-                .expect("Failed to remove old filter");
+    pub fn current_uri(&self) -> Option<String> {
+        match self.backend {
+            Backend::Playbin => self.pipeline.property::<Option<String>>("current-uri"),
+            Backend::Decodebin => None,
         }
-        if let Err(err) = Element::link_many([&self.vide_convert, &self.video_sink]) {
-            eprintln!("Failed to relink filter into pipeline: {err:?}");
+    }
+
+    fn add_source(&self, uri: &str, elements: &DecodebinElements) -> Result<(), GstreamerError> {
+        let kind = SourceKind::from_uri(uri);
+        let source = make_source(uri)?;
+        self.pipeline.add(&source)?;
+
+        elements.sources.lock().unwrap().push(source.clone());
+
+        let video_convert_clone = self.vide_convert.clone();
+        let selectors_clone = elements.selectors.clone();
+        let branches = UnlinkedBranches::new(
+            self.vide_convert.clone(),
+            elements.audio_convert.clone(),
+            elements.selectors.clone(),
+        );
+
+        if kind.has_dynamic_pads() {
+            // Every stream the source finds gets its own decodebin
+            branches.wait_for(&source);
+            let sources_clone = elements.sources.clone();
+            source.connect_pad_added(move |source, src_pad| {
+                match link_to_new_decodebin(
+                    source,
+                    src_pad,
+                    &video_convert_clone,
+                    &selectors_clone,
+                    &branches,
+                ) {
+                    Ok(decode) => sources_clone.lock().unwrap().push(decode),
+                    Err(err) => {
+                        glib::g_warning!(LOG_DOMAIN, "Failed to decode {}: {err}", src_pad.name())
+                    }
+                }
+            });
         } else {
-            println!("Filter removed successfully");
-            self.filter = None;
+            let decode = make_decodebin(video_convert_clone, selectors_clone)?;
+            branches.wait_for(&decode);
+            set_element_property(&decode, "use-buffering", kind.is_network())?;
+            self.pipeline.add(&decode)?;
+            elements.sources.lock().unwrap().push(decode.clone());
+            Element::link_many([&source, &decode])
+                .map_err(|_| GstreamerError::Link("src → decode".to_string()))?;
+        }
+
+        glib::g_info!(LOG_DOMAIN, "Using {} for '{uri}'", kind.factory_name());
+        Ok(())
+    }
+
+    pub fn is_live(&self) -> bool {
+        source::is_live(&self.pipeline)
+    }
+
+    pub fn filters(&self) -> Vec<FilterStage> {
+        self.filter_chain.stages()
+    }
+
+    pub fn filter_names(&self) -> Vec<String> {
+        self.filter_chain.names()
+    }
+
+    // The filter changes call `done` once the chain is relinked, see `FilterChain`
+    pub fn add_filter(
+        &self,
+        filter_name: &str,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.filter_chain.add(filter_name, done)
+    }
+
+    pub fn remove_filter(
+        &self,
+        index: usize,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.filter_chain.remove(index, done)
+    }
+
+    pub fn move_filter(
+        &self,
+        from: usize,
+        to: usize,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.filter_chain.move_stage(from, to, done)
+    }
+
+    // Replaces the whole chain, e.g. when loading a saved one
+    pub fn set_filters(
+        &self,
+        filter_names: &[String],
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.filter_chain.set(filter_names, done)
+    }
+
+    pub fn clear_filters(
+        &self,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.filter_chain.clear(done)
+    }
+
+    pub fn set_filter_stages(
+        &self,
+        stages: Vec<FilterStage>,
+        done: impl FnOnce(Result<(), GstreamerError>) + 'static,
+    ) -> Result<(), GstreamerError> {
+        self.filter_chain.replace(stages, done)
+    }
+
+    pub fn tracks(&self, kind: TrackKind) -> Vec<String> {
+        match (&self.decodebin, &self.streams) {
+            (Some(elements), _) => elements.selectors.tracks(kind),
+            (None, Some(streams)) => streams.tracks(kind),
+            (None, None) => Vec::new(),
+        }
+    }
+
+    pub fn selected_track(&self, kind: TrackKind) -> Option<usize> {
+        match (&self.decodebin, &self.streams) {
+            (Some(elements), _) => elements.selectors.selected(kind),
+            (None, Some(streams)) => streams.selected(kind),
+            (None, None) => None,
         }
     }
 
-    pub fn set_filter_and_add_to_pipeline(&mut self, filter_name: &str) {
-        if let Some(old_filter) = self.filter.take() {
-            old_filter
-                .set_state(gst::State::Null)
-                .expect("Failed to set old filter to NULL");
+    // `None` turns the subtitles off
+    pub fn select_track(
+        &self,
+        kind: TrackKind,
+        index: Option<usize>,
+    ) -> Result<(), GstreamerError> {
+        match (&self.decodebin, &self.streams) {
+            (Some(elements), _) => elements.selectors.select(kind, index),
+            (None, Some(streams)) => streams.select(self.pipeline.upcast_ref(), kind, index),
+            (None, None) => Ok(()),
+        }
+    }
 
+    pub fn subtitle_file(&self) -> Option<PathBuf> {
+        match &self.decodebin {
+            Some(elements) => elements.external_subtitles.path(),
+            None => self
+                .pipeline
+                .property::<Option<String>>("suburi")
+                .and_then(|suburi| local_path(&suburi)),
+        }
+    }
+
+    // `None` drops the subtitle file
+    pub fn load_subtitles(&self, path: Option<&Path>) -> Result<(), GstreamerError> {
+        let Some(elements) = &self.decodebin else {
+            return self.set_playbin_subtitles(path);
+        };
+
+        let bin = self.pipeline.upcast_ref();
+        let Some(path) = path else {
+            return elements.external_subtitles.unload(bin);
+        };
+        let style = self.subtitle_style();
+        elements.external_subtitles.load(bin, path, &style)?;
+
+        // A running pipeline only feeds the new file from where it is after a seek
+        if self.pipeline.current_state() >= gst::State::Paused {
+            if let Some(position) = self.pipeline.query_position::<gst::ClockTime>() {
+                self.seek(position, gst::SeekFlags::ACCURATE)?;
+            }
+        }
+        Ok(())
+    }
+
+    // playbin3 only picks up a new subtitle URI when it starts over, so a
+    // running pipeline is restarted at the same position
+    fn set_playbin_subtitles(&self, path: Option<&Path>) -> Result<(), GstreamerError> {
+        let suburi = path
+            .map(|path| to_uri(&path.to_string_lossy()))
+            .transpose()?;
+        let state = self.pipeline.current_state();
+        let position = self.pipeline.query_position::<gst::ClockTime>();
+        let set_state = |state: gst::State| {
             self.pipeline
-                .remove(&old_filter)
-                .expect("Failed to remove old filter");
+                .set_state(state)
+                .map(|_| ())
+                .map_err(|_| GstreamerError::StateChange {
+                    element: self.pipeline.name().to_string(),
+                    state,
+                })
+        };
+
+        if state > gst::State::Ready {
+            set_state(gst::State::Ready)?;
+        }
+        set_element_property(self.pipeline.upcast_ref(), "suburi", suburi)?;
+        if state > gst::State::Ready {
+            set_state(state)?;
+            let _ = self.pipeline.state(PLAYBIN_RESTART_TIMEOUT);
+            if let Some(position) = position {
+                self.seek(position, gst::SeekFlags::ACCURATE)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn subtitle_style(&self) -> SubtitleStyle {
+        *self.subtitle_style.lock().unwrap()
+    }
+
+    // Font size and offset apply to embedded subtitles too, the position only
+    // to subtitle files drawn by the decodebin backend. ASS/SSA files drawn by
+    // assrender only take the offset.
+    pub fn set_subtitle_style(&self, style: SubtitleStyle) -> Result<(), GstreamerError> {
+        match &self.decodebin {
+            Some(elements) => {
+                let subtitle_overlay = &elements.selectors.subtitle_overlay;
+                set_element_property(subtitle_overlay, "font-desc", style.font_desc())?;
+                set_element_property(subtitle_overlay, "subtitle-ts-offset", style.offset_ns())?;
+                elements.external_subtitles.set_style(&style)?;
+            }
+            None => {
+                if style.position != SubtitlePosition::default() {
+                    return Err(GstreamerError::Unsupported(
+                        "Moving subtitles needs the decodebin backend".to_string(),
+                    ));
+                }
+                let playbin = self.pipeline.upcast_ref();
+                set_element_property(playbin, "subtitle-font-desc", style.font_desc())?;
+                set_element_property(playbin, "text-offset", style.offset_ns())?;
+            }
         }
+        *self.subtitle_style.lock().unwrap() = style;
+        Ok(())
+    }
 
-        let new_filter_element = ElementFactory::make(filter_name)
-            .build()
-            .unwrap_or_else(|_| panic!("Could not create {}", filter_name));
+    pub fn set_orientation(
+        &self,
+        rotation: Rotation,
+        mirrored: bool,
+    ) -> Result<(), GstreamerError> {
+        self.transform.set_orientation(rotation, mirrored)
+    }
 
-        self.pipeline
-            .add(&new_filter_element)
-            .expect("Error adding filter to pipeline");
-        new_filter_element
-            .sync_state_with_parent()
-            .expect("Failed to sync filter state");
+    pub fn set_crop(&self, aspect_ratio: AspectRatio) -> Result<(), GstreamerError> {
+        self.transform.set_crop(aspect_ratio)
+    }
+
+    pub fn set_aspect_ratio(&self, aspect_ratio: AspectRatio) {
+        self.transform.set_aspect(aspect_ratio);
+    }
+
+    // Last frame the video sink rendered, after the filters
+    pub fn snapshot(&self) -> Result<Frame, GstreamerError> {
+        if self.video_sink.find_property("last-sample").is_none() {
+            return Err(GstreamerError::Unsupported(format!(
+                "{} doesn't keep the last frame",
+                self.video_sink.name()
+            )));
+        }
+        let sample = self
+            .video_sink
+            .property::<Option<gst::Sample>>("last-sample")
+            .ok_or_else(|| GstreamerError::Snapshot("no frame has been shown yet".to_string()))?;
+        Frame::from_sample(&sample)
+    }
+
+    // Volume in linear scale, set through StreamVolume so that both the
+    // volume element and playbin3 take it
+    pub fn set_volume(&self, linear: f64) {
+        if let Some(stream_volume) = self.volume.dynamic_cast_ref::<StreamVolume>() {
+            stream_volume.set_volume(StreamVolumeFormat::Linear, linear);
+        }
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        if let Some(stream_volume) = self.volume.dynamic_cast_ref::<StreamVolume>() {
+            stream_volume.set_mute(muted);
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        *self.rate.lock().unwrap()
+    }
+
+    // Changes the rate with a flushing seek from the current position. Without
+    // a position yet (not prerolled) the rate is only stored and picked up by
+    // the next seek, see `apply_rate`.
+    pub fn set_rate(&self, rate: f64) -> Result<(), GstreamerError> {
+        if rate == 0.0 {
+            return Err(GstreamerError::Unsupported(
+                "A playback rate of 0, use pause instead".to_string(),
+            ));
+        }
+
+        if let Some(position) = self.pipeline.query_position::<gst::ClockTime>() {
+            self.seek_with_rate(rate, position, gst::SeekFlags::ACCURATE)
+                .map_err(|err| {
+                    if rate < 0.0 {
+                        GstreamerError::Unsupported(
+                            "Reverse playback, the demuxer can't play this file backwards"
+                                .to_string(),
+                        )
+                    } else {
+                        err
+                    }
+                })?;
+        }
+        *self.rate.lock().unwrap() = rate;
+        Ok(())
+    }
 
-        self.vide_convert.unlink(&self.video_sink);
+    // Re-applies a non-default rate once a new stream has prerolled
+    pub fn apply_rate(&self) -> Result<(), GstreamerError> {
+        let rate = self.rate();
+        if rate == 1.0 {
+            return Ok(());
+        }
+        self.set_rate(rate)
+    }
 
-        if let Err(err) =
-            Element::link_many([&self.vide_convert, &new_filter_element, &self.video_sink])
+    pub fn seek(
+        &self,
+        position: gst::ClockTime,
+        flags: gst::SeekFlags,
+    ) -> Result<(), GstreamerError> {
+        self.seek_with_rate(self.rate(), position, flags)
+    }
+
+    // Forwards plays from `position` to the end, backwards from `position` to
+    // the start. With an A-B loop, the loop's ends take the place of the file's.
+    fn seek_with_rate(
+        &self,
+        rate: f64,
+        position: gst::ClockTime,
+        flags: gst::SeekFlags,
+    ) -> Result<(), GstreamerError> {
+        // The flush would go through the tee into the muxer and break the file
+        if let Some(recording) = self
+            .recording
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|recording| !recording.has_ended())
         {
-            eprintln!("Failed to relink filter into pipeline: {err:?}");
+            let reason = if recording.is_stopping() {
+                "Seeking before the recording is saved"
+            } else {
+                "Seeking while recording, stop the recording first"
+            };
+            return Err(GstreamerError::Unsupported(reason.to_string()));
+        }
+        let mut flags = flags | gst::SeekFlags::FLUSH;
+        let (position, start, (stop_type, stop)) = match self.ab_loop() {
+            Some((a, b)) => {
+                flags |= gst::SeekFlags::SEGMENT;
+                (position.clamp(a, b), a, (gst::SeekType::Set, b))
+            }
+            None => (
+                position,
+                gst::ClockTime::ZERO,
+                (gst::SeekType::End, gst::ClockTime::ZERO),
+            ),
+        };
+
+        if rate > 0.0 {
+            self.pipeline
+                .seek(rate, flags, gst::SeekType::Set, position, stop_type, stop)?;
         } else {
-            println!("Filter '{}' inserted into pipeline", filter_name);
-            self.filter = Some(new_filter_element);
+            self.pipeline.seek(
+                rate,
+                flags,
+                gst::SeekType::Set,
+                start,
+                gst::SeekType::Set,
+                position,
+            )?;
         }
+        Ok(())
     }
 
-    pub fn print_pipeline_properties(&self) {
-        println!("\nPipeline properties:");
-        for prop in self.pipeline.list_properties() {
-            println!("- {}", prop.name());
+    pub fn ab_loop(&self) -> Option<(gst::ClockTime, gst::ClockTime)> {
+        *self.ab_loop.lock().unwrap()
+    }
+
+    // Starts looping between `a` and `b` with a segment seek to the loop's start
+    pub fn set_ab_loop(&self, a: gst::ClockTime, b: gst::ClockTime) -> Result<(), GstreamerError> {
+        let (a, b) = (a.min(b), a.max(b));
+        if a == b {
+            return Err(GstreamerError::Unsupported(
+                "An A-B loop where A and B are the same position".to_string(),
+            ));
+        }
+
+        let previous = self.ab_loop.lock().unwrap().replace((a, b));
+        let start = if self.rate() < 0.0 { b } else { a };
+        self.seek(start, gst::SeekFlags::ACCURATE).inspect_err(|_| {
+            *self.ab_loop.lock().unwrap() = previous;
+        })
+    }
+
+    // Drops the SEGMENT flag again with a seek to the current position, so
+    // playback runs on to the end of the file
+    pub fn clear_ab_loop(&self) -> Result<(), GstreamerError> {
+        if self.ab_loop.lock().unwrap().take().is_none() {
+            return Ok(());
+        }
+        match self.pipeline.query_position::<gst::ClockTime>() {
+            Some(position) => self.seek(position, gst::SeekFlags::ACCURATE),
+            None => Ok(()),
         }
     }
 
-    pub fn list_elements(&self) {
-        println!("\nPipeline elements:");
+    // Called on SegmentDone. Without FLUSH the new segment queues up right
+    // behind the current one, so the loop plays without a gap.
+    pub fn repeat_ab_loop(&self) -> Result<(), GstreamerError> {
+        let Some((a, b)) = self.ab_loop() else {
+            return Ok(());
+        };
+        self.pipeline.seek(
+            self.rate(),
+            gst::SeekFlags::SEGMENT | gst::SeekFlags::ACCURATE,
+            gst::SeekType::Set,
+            a,
+            gst::SeekType::Set,
+            b,
+        )?;
+        Ok(())
+    }
+
+    // Negotiated framerate of the video going into the sink, None for still
+    // images and variable framerate streams
+    pub fn framerate(&self) -> Option<gst::Fraction> {
+        let caps = self.video_sink.static_pad("sink")?.current_caps()?;
+        let fps = gst_video::VideoInfo::from_caps(&caps).ok()?.fps();
+        (fps.numer() > 0 && fps.denom() > 0).then_some(fps)
+    }
+
+    pub fn frame_number(&self) -> Option<u64> {
+        let fps = self.framerate()?;
+        let position = self.pipeline.query_position::<gst::ClockTime>()?;
+        // Rounded, frame timestamps are often a nanosecond off
+        let numer = position.nseconds() as u128 * fps.numer() as u128;
+        let denom = gst::ClockTime::SECOND.nseconds() as u128 * fps.denom() as u128;
+        Some(((numer + denom / 2) / denom) as u64)
+    }
+
+    // One frame forward, pausing first when playing
+    pub fn step_forward(&self) -> Result<(), GstreamerError> {
+        self.pause_for_step()?;
+        let step = gst::event::Step::new(gst::format::Buffers::ONE, 1.0, true, false);
+        if !self.video_sink.send_event(step) {
+            return Err(GstreamerError::Unsupported(
+                "Frame stepping, the video sink ignored the step".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Steps can't go backwards, so this is an accurate seek to one frame
+    // duration before the current position
+    pub fn step_backward(&self) -> Result<(), GstreamerError> {
+        let fps = self.framerate().ok_or_else(|| {
+            GstreamerError::Unsupported("Stepping back without a fixed framerate".to_string())
+        })?;
+        let Some(position) = self.pipeline.query_position::<gst::ClockTime>() else {
+            return Ok(());
+        };
+        let frame_duration = gst::ClockTime::SECOND
+            .mul_div_floor(fps.denom() as u64, fps.numer() as u64)
+            .unwrap_or(gst::ClockTime::ZERO);
+
+        self.pause_for_step()?;
+        self.seek(
+            position.saturating_sub(frame_duration),
+            gst::SeekFlags::ACCURATE,
+        )
+    }
+
+    fn pause_for_step(&self) -> Result<(), GstreamerError> {
+        if self.pipeline.current_state() != gst::State::Playing {
+            return Ok(());
+        }
+        self.pipeline
+            .set_state(gst::State::Paused)
+            .map_err(|_| GstreamerError::StateChange {
+                element: self.pipeline.name().to_string(),
+                state: gst::State::Paused,
+            })?;
+        Ok(())
+    }
+
+    // Back to the start, which is the end of the file when playing backwards
+    pub fn rewind(&self) -> Result<(), GstreamerError> {
+        let position = if self.rate() < 0.0 {
+            self.pipeline
+                .query_duration::<gst::ClockTime>()
+                .unwrap_or(gst::ClockTime::ZERO)
+        } else {
+            gst::ClockTime::ZERO
+        };
+        self.seek(position, gst::SeekFlags::KEY_UNIT)
+    }
+
+    // Only shown with G_MESSAGES_DEBUG=gst-video-player
+    pub fn log_pipeline_properties(&self) {
+        let names: Vec<_> = self
+            .pipeline
+            .list_properties()
+            .iter()
+            .map(|prop| prop.name())
+            .collect();
+        glib::g_debug!(LOG_DOMAIN, "Pipeline properties: {}", names.join(", "));
+    }
+
+    pub fn log_elements(&self) {
+        let mut names = Vec::new();
         for element in self.pipeline.iterate_elements() {
-            println!("- {}", element.unwrap().name());
+            match element {
+                Ok(element) => names.push(element.name()),
+                // The pipeline changed while iterating, the list is incomplete
+                Err(err) => {
+                    glib::g_debug!(LOG_DOMAIN, "Stopped listing elements: {err}");
+                    break;
+                }
+            }
+        }
+        glib::g_debug!(LOG_DOMAIN, "Pipeline elements: {}", names.join(", "));
+    }
+}
+
+pub fn make_element(factory_name: &str) -> Result<Element, GstreamerError> {
+    ElementFactory::make(factory_name)
+        .build()
+        .map_err(|_| GstreamerError::MissingElement(factory_name.to_string()))
+}
+
+fn make_decodebin(
+    video_convert: Element,
+    selectors: TrackSelectors,
+) -> Result<Element, GstreamerError> {
+    let decode = make_element(DECODE)?;
+    decode.connect_pad_added(move |_dbin, src_pad| {
+        link_decoded_pad(src_pad, &video_convert, &selectors);
+    });
+    Ok(decode)
+}
+
+// A branch nothing got linked to would keep its sink (or muxer) waiting forever,
+// e.g. the video branch of a file without video. Those are ended once the
+// source and every decodebin behind it have exposed all their pads.
+#[derive(Clone)]
+struct UnlinkedBranches {
+    video_convert: Element,
+    audio_convert: Element,
+    selectors: TrackSelectors,
+    // Elements that may still add pads
+    pending: Arc<Mutex<usize>>,
+}
+
+impl UnlinkedBranches {
+    fn new(video_convert: Element, audio_convert: Element, selectors: TrackSelectors) -> Self {
+        Self {
+            video_convert,
+            audio_convert,
+            selectors,
+            pending: Arc::new(Mutex::new(0)),
         }
     }
+
+    fn wait_for(&self, element: &Element) {
+        *self.pending.lock().unwrap() += 1;
+        let branches = self.clone();
+        element.connect_no_more_pads(move |_| branches.element_done());
+    }
+
+    fn element_done(&self) {
+        {
+            let mut pending = self.pending.lock().unwrap();
+            *pending = pending.saturating_sub(1);
+            if *pending > 0 {
+                return;
+            }
+        }
+
+        let has_video = self
+            .video_convert
+            .static_pad("sink")
+            .is_some_and(|sink_pad| sink_pad.is_linked());
+        for (convert, has_stream) in [
+            (&self.video_convert, has_video),
+            (&self.audio_convert, self.selectors.has_audio()),
+        ] {
+            if has_stream {
+                continue;
+            }
+            glib::g_debug!(
+                LOG_DOMAIN,
+                "No stream for {}, ending its branch",
+                convert.name()
+            );
+            // Sent to the element, the EOS would only go through a linked sink pad
+            match convert.static_pad("sink") {
+                Some(sink_pad) => {
+                    sink_pad.send_event(gst::event::Eos::new());
+                }
+                None => glib::g_warning!(LOG_DOMAIN, "{} has no sink pad", convert.name()),
+            }
+        }
+    }
+}
+
+fn link_to_new_decodebin(
+    source: &Element,
+    src_pad: &gst::Pad,
+    video_convert: &Element,
+    selectors: &TrackSelectors,
+    branches: &UnlinkedBranches,
+) -> Result<Element, GstreamerError> {
+    let bin = source
+        .parent()
+        .and_downcast::<gst::Bin>()
+        .ok_or_else(|| glib::bool_error!("{} is not inside a bin", source.name()))?;
+
+    let decode = make_decodebin(video_convert.clone(), selectors.clone())?;
+    bin.add(&decode)?;
+    decode.sync_state_with_parent()?;
+
+    let sink_pad = decode
+        .static_pad("sink")
+        .ok_or_else(|| glib::bool_error!("decodebin has no sink pad"))?;
+    // Nothing reaches the decodebin before the link, so it can't be done yet
+    branches.wait_for(&decode);
+    src_pad
+        .link(&sink_pad)
+        .map_err(|err| GstreamerError::Link(format!("{} → decode: {err:?}", src_pad.name())))?;
+    Ok(decode)
+}
+
+fn link_decoded_pad(src_pad: &gst::Pad, video_convert: &Element, selectors: &TrackSelectors) {
+    let Some(caps) = src_pad.current_caps() else {
+        glib::g_warning!(LOG_DOMAIN, "Failed to get caps for pad");
+        return;
+    };
+    let Some(structure) = caps.structure(0) else {
+        glib::g_warning!(LOG_DOMAIN, "Failed to get structure for caps");
+        return;
+    };
+
+    let name = structure.name();
+
+    // Every audio and subtitle stream can be picked, video plays the first one
+    if !name.starts_with("video/") {
+        if let Err(err) = selectors.link_decoded_pad(name, src_pad) {
+            glib::g_warning!(LOG_DOMAIN, "Failed to link {name} stream: {err}");
+        }
+        return;
+    }
+
+    let Some(sink_pad) = video_convert.static_pad("sink") else {
+        glib::g_warning!(
+            LOG_DOMAIN,
+            "Failed to get sink pad from {}",
+            video_convert.name()
+        );
+        return;
+    };
+
+    if sink_pad.is_linked() {
+        return;
+    }
+
+    if let Err(err) = src_pad.link(&sink_pad) {
+        glib::g_warning!(LOG_DOMAIN, "Pad link failed: {err:?}");
+    }
+}
+
+pub fn add_ghost_pad(
+    bin: &gst::Bin,
+    element: &Element,
+    pad_name: &str,
+) -> Result<(), GstreamerError> {
+    let pad = element
+        .static_pad(pad_name)
+        .ok_or_else(|| glib::bool_error!("{} has no {pad_name} pad", element.name()))?;
+    let ghost_pad = gst::GhostPad::with_target(&pad)?;
+    bin.add_pad(&ghost_pad)?;
+    Ok(())
+}
+
+pub fn set_element_property(
+    element: &Element,
+    property: &str,
+    value: impl Into<glib::Value>,
+) -> Result<(), GstreamerError> {
+    let value = value.into();
+    let property_error = |reason: String| GstreamerError::Property {
+        element: element.name().to_string(),
+        property: property.to_string(),
+        reason,
+    };
+
+    let Some(pspec) = element.find_property(property) else {
+        return Err(property_error("no such property".to_string()));
+    };
+    if !pspec.flags().contains(glib::ParamFlags::WRITABLE) {
+        return Err(property_error("property is not writable".to_string()));
+    }
+    if !value.type_().is_a(pspec.value_type()) {
+        return Err(property_error(format!(
+            "expected {}, got {}",
+            pspec.value_type(),
+            value.type_()
+        )));
+    }
+
+    element.set_property_from_value(property, &value);
+    Ok(())
+}
+
+pub fn set_element_property_from_str(
+    element: &Element,
+    property: &str,
+    value: &str,
+) -> Result<(), GstreamerError> {
+    let Some(pspec) = element.find_property(property) else {
+        return Err(GstreamerError::Property {
+            element: element.name().to_string(),
+            property: property.to_string(),
+            reason: "no such property".to_string(),
+        });
+    };
+    let value = glib::Value::deserialize(value, pspec.value_type()).map_err(|_| {
+        GstreamerError::Property {
+            element: element.name().to_string(),
+            property: property.to_string(),
+            reason: format!("can't parse '{value}' as {}", pspec.value_type()),
+        }
+    })?;
+
+    set_element_property(element, property, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+    use crate::{encoding::Container, test_media};
+
+    // The decodebin graph with fakesinks, so it runs without a display. The
+    // bus watch needs a main context owned by the test's thread.
+    fn with_headless_manager(test: impl FnOnce(GstreamerManager)) {
+        test_media::init();
+        glib::MainContext::new()
+            .with_thread_default(|| {
+                let pipeline = Pipeline::new();
+                let bus = BusWatcher::new(&pipeline).unwrap();
+                let manager = GstreamerManager::with_decodebin_sinks(
+                    pipeline,
+                    bus,
+                    make_element("fakesink").unwrap(),
+                    make_element("fakesink").unwrap(),
+                )
+                .unwrap();
+                test(manager.clone());
+                manager.pipeline.set_state(gst::State::Null).unwrap();
+            })
+            .unwrap();
+    }
+
+    fn source_factories(manager: &GstreamerManager) -> Vec<String> {
+        let mut factories: Vec<String> = manager
+            .pipeline
+            .children()
+            .iter()
+            .filter_map(|element| element.factory())
+            .map(|factory| factory.name().to_string())
+            .filter(|name| ["filesrc", "souphttpsrc", "decodebin"].contains(&name.as_str()))
+            .collect();
+        factories.sort();
+        factories
+    }
+
+    #[test]
+    fn set_uri_replaces_every_source_element() {
+        let clip = test_media::write_clip("set-uri.mkv");
+        let file_uri = test_media::uri(&clip);
+        let http_uri = test_media::serve_http(fs::read(&clip).unwrap());
+
+        with_headless_manager(|mut manager| {
+            manager.create_pipeline(&http_uri).unwrap();
+            assert_eq!(source_factories(&manager), ["decodebin", "souphttpsrc"]);
+
+            // However often the kind changes, only the last source is left
+            for _ in 0..3 {
+                manager.set_uri(&file_uri).unwrap();
+                assert_eq!(source_factories(&manager), ["decodebin", "filesrc"]);
+                manager.set_uri(&http_uri).unwrap();
+                assert_eq!(source_factories(&manager), ["decodebin", "souphttpsrc"]);
+            }
+            test_media::preroll(&manager.pipeline).unwrap();
+        });
+    }
+
+    #[test]
+    fn audio_only_files_end_the_video_branch() {
+        let clip = test_media::write_audio_clip("audio-only.mkv");
+
+        with_headless_manager(|mut manager| {
+            manager.create_pipeline(&test_media::uri(&clip)).unwrap();
+            // The video fakesink only prerolls once its branch got the EOS
+            test_media::preroll(&manager.pipeline).unwrap();
+            test_media::run_to_eos(&manager.pipeline).unwrap();
+        });
+    }
+
+    #[test]
+    fn snapshot_is_the_shown_frame() {
+        let clip = test_media::write_clip("snapshot.mkv");
+
+        with_headless_manager(|mut manager| {
+            manager.create_pipeline(&test_media::uri(&clip)).unwrap();
+            test_media::preroll(&manager.pipeline).unwrap();
+            let frame = manager.snapshot().unwrap();
+
+            assert_eq!(
+                (frame.width, frame.height),
+                (test_media::CLIP_WIDTH, test_media::CLIP_HEIGHT)
+            );
+            assert_eq!(frame.stride(), test_media::CLIP_WIDTH as usize * 3);
+            assert_eq!(frame.data.len(), frame.stride() * frame.height as usize);
+
+            // The middle pixel, give or take the rounding of a YUV round trip
+            let (x, y) = (frame.width as usize / 2, frame.height as usize / 2);
+            let offset = y * frame.stride() + x * 3;
+            let pixel = &frame.data[offset..offset + 3];
+            for (channel, expected) in pixel.iter().zip(test_media::CLIP_COLOR) {
+                assert!(channel.abs_diff(expected) <= 4, "{pixel:?}");
+            }
+        });
+    }
+
+    #[test]
+    fn seeks_wait_for_the_recording_to_stop() {
+        let clip = test_media::write_clip("recording.mkv");
+        let location = test_media::temp_path("recording.webm");
+
+        with_headless_manager(|mut manager| {
+            manager.create_pipeline(&test_media::uri(&clip)).unwrap();
+            test_media::preroll(&manager.pipeline).unwrap();
+            manager
+                .start_recording(
+                    &EncodingOptions::for_container(Container::Webm),
+                    location.to_string_lossy().as_ref(),
+                )
+                .unwrap();
+            assert!(manager.is_recording());
+
+            let position = gst::ClockTime::from_mseconds(500);
+            assert!(manager.seek(position, gst::SeekFlags::ACCURATE).is_err());
+            assert!(manager.set_rate(2.0).is_err());
+
+            // Hold the EOS between the muxer and the file
+            let file_sink = manager
+                .pipeline
+                .by_name("recording-bin")
+                .and_downcast::<gst::Bin>()
+                .unwrap()
+                .iterate_recurse()
+                .into_iter()
+                .flatten()
+                .find(|element| {
+                    element
+                        .factory()
+                        .is_some_and(|factory| factory.name() == "filesink")
+                })
+                .unwrap();
+            let muxer_pad = file_sink.static_pad("sink").unwrap().peer().unwrap();
+            let probe = muxer_pad
+                .add_probe(gst::PadProbeType::BLOCK_DOWNSTREAM, |_, _| {
+                    gst::PadProbeReturn::Ok
+                })
+                .unwrap();
+
+            manager.stop_recording();
+            assert!(!manager.is_recording());
+            assert!(manager.seek(position, gst::SeekFlags::ACCURATE).is_err());
+            assert!(manager
+                .start_recording(
+                    &EncodingOptions::for_container(Container::Webm),
+                    location.to_string_lossy().as_ref(),
+                )
+                .is_err());
+
+            // Once the file is complete, seeking works again
+            muxer_pad.remove_probe(probe);
+            let deadline = Instant::now() + test_media::REQUEST_TIMEOUT;
+            while manager.seek(position, gst::SeekFlags::ACCURATE).is_err() {
+                assert!(Instant::now() < deadline, "The recording never ended");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+    }
 }
@@ -1,11 +1,56 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gstreamer::Backend;
 use gtk::gio;
 use gtk::prelude::*;
 use gtk::Application;
+use playlist::Playlist;
+use snapshot::SnapshotOptions;
 use window::WindowManager;
 
+mod bus;
+mod capture_controls;
+mod chains;
+mod cli;
+mod config;
+mod encoding;
+mod error;
+mod error_banner;
 mod file_metadata;
+mod filter_chain;
+mod filter_panel;
 mod filters;
+mod fullscreen;
 mod gstreamer;
+mod playlist;
+mod playlist_panel;
+mod preset_bar;
+mod presets;
+mod probe;
+mod property_editor;
+mod recording;
+mod render;
+mod seek;
+mod seek_bar;
+mod settings;
+mod shortcuts;
+mod snapshot;
+mod source;
+mod speed;
+mod subtitle_menu;
+mod subtitles;
+#[cfg(test)]
+mod test_media;
+mod track_controls;
+mod tracks;
+mod transform;
+mod transform_menu;
+mod video_info;
+mod volume;
+mod volume_controls;
 mod window;
 
 const APP_ID: &str = "org.gtk_rs.gst_video_player";
@@ -13,29 +58,97 @@ const TITLE: &str = "Mini video player GStreamer + GTK4";
 const WIDTH: i32 = 1280;
 const HEIGHT: i32 = 720;
 const CSS_PATH: &str = "assets/style.css";
+// Info and debug messages show up with G_MESSAGES_DEBUG=gst-video-player
+const LOG_DOMAIN: &str = "gst-video-player";
 
 fn main() -> Result<(), String> {
-    println!("GTK4 Video Player with Gstreamer in Rust!");
+    // On stderr, so --probe output on stdout stays valid JSON
+    eprintln!("GTK4 Video Player with Gstreamer in Rust!");
 
     let app = Application::builder()
         .application_id(APP_ID)
         .flags(gio::ApplicationFlags::HANDLES_OPEN)
         .build();
 
-    app.connect_open(|app, files, _hint| {
-        gst::init().expect("Failed to init GStreamer");
-        if let Some(file) = files.first() {
-            if let Some(path) = file.path() {
-                let window_manager = WindowManager::new(
-                    TITLE.to_string(),
-                    WIDTH,
-                    HEIGHT,
-                    path.to_str().expect("Error on file path").to_string(),
-                    CSS_PATH.to_string(),
-                );
-                window_manager.build(app);
+    cli::add_options(&app);
+
+    let backend = Rc::new(Cell::new(Backend::default()));
+    let snapshot_options = Rc::new(RefCell::new(SnapshotOptions::default()));
+
+    let backend_clone = backend.clone();
+    let snapshot_options_clone = snapshot_options.clone();
+    app.connect_handle_local_options(move |_app, options| {
+        match cli::backend(options) {
+            Ok(Some(selected)) => backend_clone.set(selected),
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("{err}");
+                return 1;
+            }
+        }
+        match cli::snapshot_options(options) {
+            Ok(selected) => snapshot_options_clone.replace(selected),
+            Err(err) => {
+                eprintln!("{err}");
+                return 1;
+            }
+        };
+
+        // Headless modes exit before any window is created
+        match cli::probe_input(options) {
+            Ok(Some(input)) => {
+                return match probe::run(&input) {
+                    Ok(()) => 0,
+                    Err(err) => {
+                        eprintln!("Probe failed: {err:#}");
+                        1
+                    }
+                };
+            }
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("{err}");
+                return 1;
             }
         }
+        match cli::render_options(options) {
+            Ok(Some(render_options)) => {
+                return match render::run(&render_options) {
+                    Ok(()) => 0,
+                    Err(err) => {
+                        eprintln!("Render failed: {err:#}");
+                        1
+                    }
+                };
+            }
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("{err}");
+                return 1;
+            }
+        }
+
+        // Keep going with the default handling
+        -1
+    });
+
+    app.connect_open(move |app, files, _hint| {
+        gst::init().expect("Failed to init GStreamer");
+        if files.is_empty() {
+            return;
+        }
+
+        let playlist = Playlist::new(files.iter().map(|file| file.uri().to_string()).collect());
+        let window_manager = WindowManager::new(
+            TITLE.to_string(),
+            WIDTH,
+            HEIGHT,
+            playlist,
+            CSS_PATH.to_string(),
+            backend.get(),
+            snapshot_options.borrow().clone(),
+        );
+        window_manager.build(app);
     });
 
     app.run();
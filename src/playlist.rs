@@ -0,0 +1,256 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const REPEAT_MODE_NAMES: &[&str] = &["Repeat off", "Repeat all", "Repeat one"];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    All,
+    One,
+}
+
+impl RepeatMode {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => RepeatMode::All,
+            2 => RepeatMode::One,
+            _ => RepeatMode::Off,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Playlist {
+    items: Vec<String>,
+    // Play order as indices into `items`, shuffled or not
+    order: Vec<usize>,
+    position: usize,
+    repeat: RepeatMode,
+}
+
+impl Playlist {
+    pub fn new(items: Vec<String>) -> Self {
+        let order = (0..items.len()).collect();
+        Self {
+            items,
+            order,
+            ..Default::default()
+        }
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        self.order.get(self.position).copied()
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current_index().map(|index| self.items[index].as_str())
+    }
+
+    pub fn select(&mut self, index: usize) -> Option<&str> {
+        self.position = self.order.iter().position(|&item| item == index)?;
+        self.current()
+    }
+
+    // Explicit next/previous always wrap around, only auto advance honours repeat off
+    pub fn next(&mut self) -> Option<&str> {
+        if self.order.is_empty() {
+            return None;
+        }
+        self.position = (self.position + 1) % self.order.len();
+        self.current()
+    }
+
+    pub fn previous(&mut self) -> Option<&str> {
+        if self.order.is_empty() {
+            return None;
+        }
+        self.position = (self.position + self.order.len() - 1) % self.order.len();
+        self.current()
+    }
+
+    pub fn peek_on_finish(&self) -> Option<&str> {
+        self.position_on_finish()
+            .map(|position| self.items[self.order[position]].as_str())
+    }
+
+    pub fn advance_on_finish(&mut self) -> Option<&str> {
+        self.position = self.position_on_finish()?;
+        self.current()
+    }
+
+    fn position_on_finish(&self) -> Option<usize> {
+        if self.order.is_empty() {
+            return None;
+        }
+        match self.repeat {
+            RepeatMode::One => Some(self.position),
+            RepeatMode::All => Some((self.position + 1) % self.order.len()),
+            RepeatMode::Off => Some(self.position + 1).filter(|&next| next < self.order.len()),
+        }
+    }
+
+    pub fn set_repeat(&mut self, repeat: RepeatMode) {
+        self.repeat = repeat;
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        let current = self.current_index();
+        self.order = (0..self.items.len()).collect();
+
+        if shuffle {
+            shuffle_indices(&mut self.order);
+            // Keep playing the current item and shuffle what comes after it
+            if let Some(current) = current {
+                if let Some(index) = self.order.iter().position(|&item| item == current) {
+                    self.order.swap(0, index);
+                }
+            }
+            self.position = 0;
+        } else {
+            self.position = current.unwrap_or(0);
+        }
+    }
+}
+
+pub fn display_name(uri: &str) -> String {
+    let name = uri.trim_end_matches('/').rsplit('/').next().unwrap_or(uri);
+    glib::Uri::unescape_string(name, None::<&str>)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+fn shuffle_indices(indices: &mut [usize]) {
+    // xorshift seeded from the clock, good enough to mix up a playlist
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0x2545_f491_4f6c_dd1d)
+        | 1;
+
+    for i in (1..indices.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = (state % (i as u64 + 1)) as usize;
+        indices.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist(len: usize) -> Playlist {
+        Playlist::new(
+            (0..len)
+                .map(|index| format!("file:///{index}.mkv"))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let mut playlist = playlist(3);
+        assert_eq!(playlist.current(), Some("file:///0.mkv"));
+        assert_eq!(playlist.previous(), Some("file:///2.mkv"));
+        assert_eq!(playlist.next(), Some("file:///0.mkv"));
+        assert_eq!(playlist.next(), Some("file:///1.mkv"));
+        assert_eq!(playlist.next(), Some("file:///2.mkv"));
+        assert_eq!(playlist.next(), Some("file:///0.mkv"));
+    }
+
+    #[test]
+    fn empty_playlist_has_nothing_to_play() {
+        let mut playlist = playlist(0);
+        assert_eq!(playlist.current(), None);
+        assert_eq!(playlist.next(), None);
+        assert_eq!(playlist.previous(), None);
+        assert_eq!(playlist.peek_on_finish(), None);
+        assert_eq!(playlist.advance_on_finish(), None);
+        playlist.set_shuffle(true);
+        assert_eq!(playlist.current(), None);
+    }
+
+    #[test]
+    fn repeat_off_stops_at_the_end() {
+        let mut playlist = playlist(2);
+        assert_eq!(playlist.peek_on_finish(), Some("file:///1.mkv"));
+        assert_eq!(playlist.advance_on_finish(), Some("file:///1.mkv"));
+        assert_eq!(playlist.peek_on_finish(), None);
+        assert_eq!(playlist.advance_on_finish(), None);
+        // Running out leaves the last item current
+        assert_eq!(playlist.current(), Some("file:///1.mkv"));
+    }
+
+    #[test]
+    fn repeat_all_starts_over() {
+        let mut playlist = playlist(2);
+        playlist.set_repeat(RepeatMode::All);
+        assert_eq!(playlist.advance_on_finish(), Some("file:///1.mkv"));
+        assert_eq!(playlist.peek_on_finish(), Some("file:///0.mkv"));
+        assert_eq!(playlist.advance_on_finish(), Some("file:///0.mkv"));
+    }
+
+    #[test]
+    fn repeat_one_stays_on_the_item() {
+        let mut playlist = playlist(3);
+        playlist.select(1);
+        playlist.set_repeat(RepeatMode::One);
+        assert_eq!(playlist.advance_on_finish(), Some("file:///1.mkv"));
+        assert_eq!(playlist.advance_on_finish(), Some("file:///1.mkv"));
+        // Explicit next still moves on
+        assert_eq!(playlist.next(), Some("file:///2.mkv"));
+    }
+
+    #[test]
+    fn shuffle_keeps_the_current_item_and_plays_everything_once() {
+        let mut playlist = playlist(20);
+        playlist.select(7);
+        playlist.set_shuffle(true);
+        assert_eq!(playlist.current_index(), Some(7));
+
+        let mut played = vec![7];
+        while playlist.advance_on_finish().is_some() {
+            played.push(playlist.current_index().unwrap());
+        }
+        played.sort();
+        assert_eq!(played, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn unshuffle_continues_in_order_from_the_current_item() {
+        let mut playlist = playlist(5);
+        playlist.set_shuffle(true);
+        playlist.next();
+        let current = playlist.current_index().unwrap();
+        playlist.set_shuffle(false);
+        assert_eq!(playlist.current_index(), Some(current));
+        assert_eq!(
+            playlist.next().map(str::to_string),
+            Some(format!("file:///{}.mkv", (current + 1) % 5))
+        );
+    }
+
+    #[test]
+    fn select_ignores_unknown_items() {
+        let mut playlist = playlist(2);
+        assert_eq!(playlist.select(5), None);
+        assert_eq!(playlist.current(), Some("file:///0.mkv"));
+        assert_eq!(playlist.select(1), Some("file:///1.mkv"));
+    }
+
+    #[test]
+    fn display_name_is_the_unescaped_file_name() {
+        assert_eq!(display_name("file:///videos/My%20clip.mkv"), "My clip.mkv");
+        assert_eq!(display_name("https://example.com/live/"), "live");
+    }
+}
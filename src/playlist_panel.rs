@@ -0,0 +1,173 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{prelude::*, ApplicationWindow, Orientation};
+
+use crate::{
+    bus::PlayerEvent,
+    error_banner::ErrorBanner,
+    gstreamer::GstreamerManager,
+    playlist::{display_name, Playlist, RepeatMode, REPEAT_MODE_NAMES},
+    video_info::VideoInfo,
+    window::{add_action, set_pipeline_state},
+};
+
+// Sidebar with the playlist, shuffle and repeat, only shown for more than one item
+#[derive(Clone)]
+pub struct PlaylistPanel {
+    container: gtk::Box,
+    playlist_list: gtk::ListBox,
+    playlist: Rc<RefCell<Playlist>>,
+    gst_manager: GstreamerManager,
+    video_info: VideoInfo,
+    error_banner: ErrorBanner,
+}
+
+impl PlaylistPanel {
+    pub fn new(
+        window: &ApplicationWindow,
+        playlist: Rc<RefCell<Playlist>>,
+        video_info: VideoInfo,
+        gst_manager: &GstreamerManager,
+        error_banner: ErrorBanner,
+    ) -> Self {
+        let shuffle_toggle = gtk::ToggleButton::with_label("Shuffle");
+        let repeat_selector = gtk::DropDown::from_strings(REPEAT_MODE_NAMES);
+        let playlist_list = gtk::ListBox::new();
+        playlist_list.set_selection_mode(gtk::SelectionMode::Single);
+        for uri in playlist.borrow().items() {
+            let item_label = gtk::Label::new(Some(&display_name(uri)));
+            item_label.set_halign(gtk::Align::Start);
+            playlist_list.append(&item_label);
+        }
+        let playlist_scroll = gtk::ScrolledWindow::builder()
+            .child(&playlist_list)
+            .min_content_width(220)
+            .vexpand(true)
+            .build();
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.set_css_classes(&["playlist"]);
+        container.append(&shuffle_toggle);
+        container.append(&repeat_selector);
+        container.append(&playlist_scroll);
+        container.set_visible(playlist.borrow().len() > 1);
+
+        let panel = Self {
+            container,
+            playlist_list: playlist_list.clone(),
+            playlist,
+            gst_manager: gst_manager.clone(),
+            video_info,
+            error_banner,
+        };
+        panel.sync();
+
+        let panel_clone = panel.clone();
+        add_action(window, "previous", move || {
+            let moved = panel_clone.playlist.borrow_mut().previous().is_some();
+            if moved {
+                panel_clone.play_current();
+            }
+        });
+
+        let panel_clone = panel.clone();
+        add_action(window, "next", move || {
+            let moved = panel_clone.playlist.borrow_mut().next().is_some();
+            if moved {
+                panel_clone.play_current();
+            }
+        });
+
+        let panel_clone = panel.clone();
+        playlist_list.connect_row_activated(move |_, row| {
+            let Ok(index) = usize::try_from(row.index()) else {
+                return;
+            };
+            let moved = panel_clone.playlist.borrow_mut().select(index).is_some();
+            if moved {
+                panel_clone.play_current();
+            }
+        });
+
+        let panel_clone = panel.clone();
+        shuffle_toggle.connect_toggled(move |toggle| {
+            panel_clone
+                .playlist
+                .borrow_mut()
+                .set_shuffle(toggle.is_active());
+            panel_clone.sync();
+        });
+
+        let panel_clone = panel.clone();
+        repeat_selector.connect_selected_notify(move |dropdown| {
+            panel_clone
+                .playlist
+                .borrow_mut()
+                .set_repeat(RepeatMode::from_index(dropdown.selected()));
+            panel_clone.sync();
+        });
+
+        // The end of stream is handled by the window, through `advance_on_finish`
+        let panel_clone = panel.clone();
+        gst_manager.bus.subscribe(move |event| {
+            if let PlayerEvent::StreamStart = event {
+                // playbin3 already switched to the queued item on about-to-finish
+                if let Some(current_uri) = panel_clone.gst_manager.current_uri() {
+                    let mut playlist = panel_clone.playlist.borrow_mut();
+                    if playlist.current() != Some(current_uri.as_str()) {
+                        playlist.advance_on_finish();
+                    }
+                }
+                panel_clone.sync();
+            }
+        });
+
+        panel
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    // Plays the next item once the current one finished, false at the end of
+    // the playlist
+    pub fn advance_on_finish(&self) -> bool {
+        let advanced = self.playlist.borrow_mut().advance_on_finish().is_some();
+        if advanced {
+            self.play_current();
+        }
+        advanced
+    }
+
+    fn play_current(&self) {
+        let Some(uri) = self.playlist.borrow().current().map(str::to_string) else {
+            return;
+        };
+        if let Err(err) = self.gst_manager.set_uri(&uri) {
+            self.error_banner.show(&err);
+            return;
+        }
+        set_pipeline_state(
+            &self.gst_manager.pipeline,
+            gst::State::Playing,
+            &self.error_banner,
+        );
+        self.sync();
+    }
+
+    // Highlights the current row and queues the following item for gapless playback
+    fn sync(&self) {
+        let playlist = self.playlist.borrow();
+        let row = playlist
+            .current_index()
+            .and_then(|index| i32::try_from(index).ok())
+            .and_then(|index| self.playlist_list.row_at_index(index));
+        let changed = row.is_some() && row != self.playlist_list.selected_row();
+        self.playlist_list.select_row(row.as_ref());
+        self.gst_manager.set_next_uri(playlist.peek_on_finish());
+
+        if changed {
+            self.video_info.close();
+        }
+    }
+}
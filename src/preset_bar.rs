@@ -0,0 +1,227 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use gtk::{gio, prelude::*, Button, Orientation};
+
+use crate::{
+    error::GstreamerError,
+    error_banner::ErrorBanner,
+    filters::FilterStage,
+    gstreamer::GstreamerManager,
+    presets::{Preset, PresetStore},
+    LOG_DOMAIN,
+};
+
+// Preset dropdown with save, delete, import and export of preset files
+#[derive(Clone)]
+pub struct PresetBar {
+    container: gtk::Box,
+    preset_list: gtk::StringList,
+    preset_selector: gtk::DropDown,
+    gst_manager: GstreamerManager,
+    presets: Rc<RefCell<Option<PresetStore>>>,
+    error_banner: ErrorBanner,
+}
+
+impl PresetBar {
+    pub fn new(
+        gst_manager: GstreamerManager,
+        error_banner: ErrorBanner,
+        on_apply: impl Fn(Vec<FilterStage>) + 'static,
+    ) -> Self {
+        let preset_list = gtk::StringList::new(&[]);
+        let preset_selector = gtk::DropDown::builder().model(&preset_list).build();
+        preset_selector.set_hexpand(true);
+        let apply_button = Button::with_label("Apply");
+        let delete_button = Button::with_label("Delete");
+
+        let select_box = gtk::Box::new(Orientation::Horizontal, 5);
+        select_box.append(&preset_selector);
+        select_box.append(&apply_button);
+        select_box.append(&delete_button);
+
+        let name_entry = gtk::Entry::new();
+        name_entry.set_placeholder_text(Some("Preset name"));
+        name_entry.set_hexpand(true);
+        let save_button = Button::with_label("Save preset");
+
+        let save_box = gtk::Box::new(Orientation::Horizontal, 5);
+        save_box.append(&name_entry);
+        save_box.append(&save_button);
+
+        let import_button = Button::with_label("Import…");
+        import_button.set_hexpand(true);
+        let export_button = Button::with_label("Export…");
+        export_button.set_hexpand(true);
+
+        let file_box = gtk::Box::new(Orientation::Horizontal, 5);
+        file_box.append(&import_button);
+        file_box.append(&export_button);
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.append(&select_box);
+        container.append(&save_box);
+        container.append(&file_box);
+
+        let presets = match PresetStore::load() {
+            Ok(presets) => Some(presets),
+            Err(err) => {
+                error_banner.show(&err);
+                container.set_sensitive(false);
+                None
+            }
+        };
+
+        let preset_bar = Self {
+            container,
+            preset_list,
+            preset_selector,
+            gst_manager,
+            presets: Rc::new(RefCell::new(presets)),
+            error_banner,
+        };
+        preset_bar.refresh(None);
+
+        let preset_bar_clone = preset_bar.clone();
+        apply_button.connect_clicked(move |_| {
+            let Some(name) = preset_bar_clone.selected_preset() else {
+                return;
+            };
+            let stages = preset_bar_clone
+                .presets
+                .borrow()
+                .as_ref()
+                .and_then(|presets| presets.get(&name))
+                .map(Preset::build_stages);
+            match stages {
+                Some(Ok(stages)) => on_apply(stages),
+                Some(Err(err)) => preset_bar_clone.error_banner.show(&err),
+                None => {}
+            }
+        });
+
+        let preset_bar_clone = preset_bar.clone();
+        delete_button.connect_clicked(move |_| {
+            let Some(name) = preset_bar_clone.selected_preset() else {
+                return;
+            };
+            preset_bar_clone.update(None, |presets| presets.remove(&name));
+        });
+
+        let preset_bar_clone = preset_bar.clone();
+        save_button.connect_clicked(move |_| {
+            let name = name_entry.text().trim().to_string();
+            if name.is_empty() {
+                preset_bar_clone
+                    .error_banner
+                    .show(&"Give the preset a name first");
+                return;
+            }
+            let preset = Preset::capture(&name, &preset_bar_clone.gst_manager.filters());
+            preset_bar_clone.update(Some(&name), |presets| presets.save(preset));
+            name_entry.set_text("");
+        });
+
+        let preset_bar_clone = preset_bar.clone();
+        import_button.connect_clicked(move |_| {
+            let preset_bar = preset_bar_clone.clone();
+            preset_bar_clone.choose_file(None, move |path| {
+                let result = preset_bar
+                    .presets
+                    .borrow_mut()
+                    .as_mut()
+                    .map(|presets| presets.import(&path));
+                match result {
+                    Some(Ok(name)) => preset_bar.refresh(Some(&name)),
+                    Some(Err(err)) => preset_bar.error_banner.show(&err),
+                    None => {}
+                }
+            });
+        });
+
+        let preset_bar_clone = preset_bar.clone();
+        export_button.connect_clicked(move |_| {
+            let Some(name) = preset_bar_clone.selected_preset() else {
+                return;
+            };
+            let preset_bar = preset_bar_clone.clone();
+            let file_name = format!("{name}.toml");
+            preset_bar_clone.choose_file(Some(&file_name), move |path| {
+                let result = match preset_bar.presets.borrow().as_ref() {
+                    Some(presets) => presets.export(&name, &path),
+                    None => return,
+                };
+                match result {
+                    Ok(()) => glib::g_info!(
+                        LOG_DOMAIN,
+                        "Exported preset '{name}' to '{}'",
+                        path.display()
+                    ),
+                    Err(err) => preset_bar.error_banner.show(&err),
+                }
+            });
+        });
+
+        preset_bar
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    fn selected_preset(&self) -> Option<String> {
+        self.preset_list
+            .string(self.preset_selector.selected())
+            .map(|name| name.to_string())
+    }
+
+    fn update(
+        &self,
+        select: Option<&str>,
+        change: impl FnOnce(&mut PresetStore) -> Result<(), GstreamerError>,
+    ) {
+        let result = match self.presets.borrow_mut().as_mut() {
+            Some(presets) => change(presets),
+            None => return,
+        };
+        if let Err(err) = result {
+            self.error_banner.show(&err);
+        }
+        self.refresh(select);
+    }
+
+    fn refresh(&self, select: Option<&str>) {
+        let presets = self.presets.borrow();
+        let names = presets.as_ref().map(PresetStore::names).unwrap_or_default();
+        self.preset_list
+            .splice(0, self.preset_list.n_items(), &names);
+
+        let position = select.and_then(|select| names.iter().position(|&name| name == select));
+        if let Some(position) = position {
+            self.preset_selector.set_selected(position as u32);
+        }
+    }
+
+    // Opens a file chooser; with `save_as` set it asks where to save instead
+    fn choose_file(&self, save_as: Option<&str>, on_chosen: impl FnOnce(PathBuf) + 'static) {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("Presets"));
+        filter.add_suffix("toml");
+
+        let dialog = gtk::FileDialog::new();
+        dialog.set_default_filter(Some(&filter));
+        let window = self.container.root().and_downcast::<gtk::Window>();
+        let on_file = move |result: Result<gio::File, glib::Error>| {
+            if let Some(path) = result.ok().and_then(|file| file.path()) {
+                on_chosen(path);
+            }
+        };
+
+        match save_as {
+            Some(file_name) => {
+                dialog.set_initial_name(Some(file_name));
+                dialog.save(window.as_ref(), gio::Cancellable::NONE, on_file);
+            }
+            None => dialog.open(window.as_ref(), gio::Cancellable::NONE, on_file),
+        }
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use gst::prelude::*;
+use toml::{Table, Value};
+
+use crate::{
+    config::{config_file, read_existing_table, write_table},
+    error::GstreamerError,
+    filters::{editable_properties, FilterStage},
+    gstreamer::set_element_property_from_str,
+    LOG_DOMAIN,
+};
+
+const PRESETS_DIR: &str = "presets";
+const PRESET_EXTENSION: &str = "toml";
+
+// A filter chain with tweaked properties, stored as
+//
+// name = "Retro"
+//
+// [[filters]]
+// name = "agingtv"
+//
+// [filters.properties]
+// scratch-lines = "12"
+#[derive(Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub filters: Vec<PresetFilter>,
+}
+
+// Property values are kept in GStreamer's serialized form
+#[derive(Debug, Clone)]
+pub struct PresetFilter {
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+impl Preset {
+    // Only properties that differ from their defaults are kept
+    pub fn capture(name: &str, stages: &[FilterStage]) -> Self {
+        let filters = stages
+            .iter()
+            .map(|stage| {
+                let properties = editable_properties(&stage.element)
+                    .iter()
+                    .filter_map(|pspec| {
+                        let value = stage
+                            .element
+                            .property_value(pspec.name())
+                            .serialize()
+                            .ok()?;
+                        let default = pspec.default_value().serialize().ok();
+                        (default.as_deref() != Some(value.as_str()))
+                            .then(|| (pspec.name().to_string(), value.to_string()))
+                    })
+                    .collect();
+                PresetFilter {
+                    name: stage.name.clone(),
+                    properties,
+                }
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            filters,
+        }
+    }
+
+    pub fn build_stages(&self) -> Result<Vec<FilterStage>, GstreamerError> {
+        self.filters
+            .iter()
+            .map(|filter| {
+                let stage = FilterStage::new(&filter.name)?;
+                for (property, value) in &filter.properties {
+                    set_element_property_from_str(&stage.element, property, value)?;
+                }
+                Ok(stage)
+            })
+            .collect()
+    }
+
+    fn from_table(table: &Table) -> Option<Self> {
+        let name = table.get("name")?.as_str()?.to_string();
+        let filters = table
+            .get("filters")
+            .and_then(Value::as_array)
+            .map(|filters| {
+                filters
+                    .iter()
+                    .filter_map(Value::as_table)
+                    .filter_map(|filter| {
+                        let name = filter.get("name")?.as_str()?.to_string();
+                        let properties = filter
+                            .get("properties")
+                            .and_then(Value::as_table)
+                            .map(|properties| {
+                                properties
+                                    .iter()
+                                    .map(|(property, value)| {
+                                        // Hand written files may use plain TOML numbers and booleans
+                                        let value = value
+                                            .as_str()
+                                            .map(str::to_string)
+                                            .unwrap_or_else(|| value.to_string());
+                                        (property.clone(), value)
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        Some(PresetFilter { name, properties })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self { name, filters })
+    }
+
+    fn to_table(&self) -> Table {
+        let filters = self
+            .filters
+            .iter()
+            .map(|filter| {
+                let properties: Table = filter
+                    .properties
+                    .iter()
+                    .map(|(property, value)| (property.clone(), Value::String(value.clone())))
+                    .collect();
+                let mut table = Table::new();
+                table.insert("name".to_string(), Value::String(filter.name.clone()));
+                table.insert("properties".to_string(), Value::Table(properties));
+                Value::Table(table)
+            })
+            .collect();
+
+        let mut table = Table::new();
+        table.insert("name".to_string(), Value::String(self.name.clone()));
+        table.insert("filters".to_string(), Value::Array(filters));
+        table
+    }
+}
+
+// One file per preset in the presets folder of the config directory
+pub struct PresetStore {
+    folder: PathBuf,
+    presets: Vec<Preset>,
+    // Where each preset was read from or saved to, which needn't match its
+    // name for imported or hand written files
+    files: BTreeMap<String, PathBuf>,
+}
+
+impl PresetStore {
+    pub fn load() -> Result<Self, GstreamerError> {
+        Ok(Self::load_from(config_file(PRESETS_DIR)?))
+    }
+
+    fn load_from(folder: PathBuf) -> Self {
+        let mut presets = Vec::new();
+        let mut files: BTreeMap<String, PathBuf> = BTreeMap::new();
+
+        if let Ok(entries) = fs::read_dir(&folder) {
+            let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+            paths.sort();
+            for path in paths {
+                if path.extension().and_then(|ext| ext.to_str()) != Some(PRESET_EXTENSION) {
+                    continue;
+                }
+                let preset = match read_preset(&path) {
+                    Ok(preset) => preset,
+                    Err(err) => {
+                        glib::g_warning!(LOG_DOMAIN, "Skipping preset: {err}");
+                        continue;
+                    }
+                };
+                if let Some(first) = files.get(&preset.name) {
+                    glib::g_warning!(
+                        LOG_DOMAIN,
+                        "Skipping preset '{}' in '{}', '{}' has the same name",
+                        preset.name,
+                        path.display(),
+                        first.display()
+                    );
+                    continue;
+                }
+                files.insert(preset.name.clone(), path);
+                presets.push(preset);
+            }
+        }
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            folder,
+            presets,
+            files,
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.presets
+            .iter()
+            .map(|preset| preset.name.as_str())
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    // A preset that already exists is saved over its own file
+    pub fn save(&mut self, preset: Preset) -> Result<(), GstreamerError> {
+        let path = self.file_for(&preset.name);
+        // Names map to file names lossily, e.g. "My Look" and "My_Look", and
+        // saving one would overwrite the other
+        if let Some(existing) = self
+            .files
+            .iter()
+            .find(|(name, file)| **name != preset.name && same_file(file, &path))
+            .map(|(name, _)| name)
+        {
+            return Err(GstreamerError::Config {
+                path: path.display().to_string(),
+                reason: format!(
+                    "'{}' would be saved in the same file as '{existing}', pick another name",
+                    preset.name
+                ),
+            });
+        }
+        write_table(&path, &preset.to_table())?;
+        self.files.insert(preset.name.clone(), path);
+        self.presets.retain(|existing| existing.name != preset.name);
+        self.presets.push(preset);
+        self.presets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), GstreamerError> {
+        let path = self.file_for(name);
+        if path.exists() {
+            fs::remove_file(&path).map_err(|err| GstreamerError::Config {
+                path: path.display().to_string(),
+                reason: err.to_string(),
+            })?;
+        }
+        self.files.remove(name);
+        self.presets.retain(|preset| preset.name != name);
+        Ok(())
+    }
+
+    // Copies a preset file into the store, returning its name
+    pub fn import(&mut self, path: &Path) -> Result<String, GstreamerError> {
+        let preset = read_preset(path)?;
+        let name = preset.name.clone();
+        self.save(preset)?;
+        Ok(name)
+    }
+
+    pub fn export(&self, name: &str, path: &Path) -> Result<(), GstreamerError> {
+        let preset = self.get(name).ok_or_else(|| GstreamerError::Config {
+            path: path.display().to_string(),
+            reason: format!("no preset named '{name}'"),
+        })?;
+        write_table(path, &preset.to_table())
+    }
+
+    fn file_for(&self, name: &str) -> PathBuf {
+        self.files
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| self.path_for(name))
+    }
+
+    fn path_for(&self, name: &str) -> PathBuf {
+        let file_stem: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.folder.join(file_stem).with_extension(PRESET_EXTENSION)
+    }
+}
+
+// Case-insensitive, like the file systems of macOS and Windows
+fn same_file(a: &Path, b: &Path) -> bool {
+    a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+}
+
+fn read_preset(path: &Path) -> Result<Preset, GstreamerError> {
+    Preset::from_table(&read_existing_table(path)?).ok_or_else(|| GstreamerError::Config {
+        path: path.display().to_string(),
+        reason: "not a preset, it needs at least a name".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_media;
+
+    fn preset(name: &str) -> Preset {
+        Preset {
+            name: name.to_string(),
+            filters: vec![PresetFilter {
+                name: "videobalance".to_string(),
+                properties: BTreeMap::from([("saturation".to_string(), "0".to_string())]),
+            }],
+        }
+    }
+
+    fn empty_store(name: &str) -> PresetStore {
+        let folder = test_media::temp_path(name);
+        fs::remove_dir_all(&folder).ok();
+        PresetStore::load_from(folder)
+    }
+
+    #[test]
+    fn saved_presets_load_again() {
+        let mut store = empty_store("presets-round-trip");
+        store.save(preset("My Look")).unwrap();
+        store.save(preset("Retro")).unwrap();
+
+        let store = PresetStore::load_from(store.folder.clone());
+        assert_eq!(store.names(), ["My Look", "Retro"]);
+        let filter = &store.get("My Look").unwrap().filters[0];
+        assert_eq!(filter.name, "videobalance");
+        assert_eq!(filter.properties["saturation"], "0");
+    }
+
+    #[test]
+    fn names_sharing_a_file_are_refused() {
+        let mut store = empty_store("presets-collisions");
+        store.save(preset("My Look")).unwrap();
+
+        assert!(store.save(preset("My_Look")).is_err());
+        assert!(store.save(preset("my look")).is_err());
+        // Saving the same preset again still replaces it
+        store.save(preset("My Look")).unwrap();
+        assert_eq!(store.names(), ["My Look"]);
+    }
+
+    #[test]
+    fn files_named_differently_are_saved_and_removed() {
+        let store = empty_store("presets-hand-written");
+        fs::create_dir_all(&store.folder).unwrap();
+        let file = store.folder.join("from-a-friend.toml");
+        fs::write(&file, "name = \"Sunset\"\n").unwrap();
+
+        let mut store = PresetStore::load_from(store.folder.clone());
+        assert_eq!(store.names(), ["Sunset"]);
+        store.save(preset("Sunset")).unwrap();
+        assert!(!store.path_for("Sunset").exists());
+        assert_eq!(read_preset(&file).unwrap().filters.len(), 1);
+
+        store.remove("Sunset").unwrap();
+        assert!(!file.exists());
+        assert!(PresetStore::load_from(store.folder.clone())
+            .names()
+            .is_empty());
+    }
+
+    #[test]
+    fn importing_a_missing_file_says_so() {
+        let mut store = empty_store("presets-import");
+        let missing = store.folder.join("missing.toml");
+
+        let GstreamerError::Config { reason, .. } = store.import(&missing).unwrap_err() else {
+            panic!("expected a config error");
+        };
+        assert!(!reason.contains("not a preset"), "{reason}");
+
+        let not_a_preset = test_media::temp_path("not-a-preset.toml");
+        fs::write(&not_a_preset, "volume = 1\n").unwrap();
+        let GstreamerError::Config { reason, .. } = store.import(&not_a_preset).unwrap_err() else {
+            panic!("expected a config error");
+        };
+        assert!(reason.contains("not a preset"), "{reason}");
+    }
+}
@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+use crate::{file_metadata::FileMetadata, source::to_uri};
+
+// Prints what the Video Info window shows as JSON on stdout. The report is
+// printed even when discovery fails, with its `error` set, but the exit
+// status still tells the failure apart.
+pub fn run(input: &str) -> anyhow::Result<()> {
+    gst::init().context("Failed to init GStreamer")?;
+
+    let uri = to_uri(input)?;
+    let file_metadata = FileMetadata::new(&uri);
+    let media = FileMetadata::discover(&uri);
+    let report = serde_json::to_string_pretty(&file_metadata.to_json(&media))?;
+    println!("{report}");
+
+    media?;
+    Ok(())
+}
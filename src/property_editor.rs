@@ -0,0 +1,396 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gst::{prelude::*, Element};
+use gtk::{prelude::*, Button, Orientation};
+
+use crate::{
+    error::GstreamerError,
+    error_banner::ErrorBanner,
+    filters::{editable_properties, FilterStage},
+    gstreamer::set_element_property,
+    LOG_DOMAIN,
+};
+
+// Wider floating point ranges get a spin button instead of a slider
+const MAX_SLIDER_RANGE: f64 = 10_000.0;
+// Integers beyond this lose precision as f64, so they get a plain entry
+const MAX_EXACT_F64: i128 = 1 << 53;
+
+// Widgets generated from the properties of the selected filter
+#[derive(Clone)]
+pub struct PropertyEditor {
+    container: gtk::Box,
+    title: gtk::Label,
+    rows: gtk::Box,
+    reset_all_button: Button,
+    element: Rc<RefCell<Option<Element>>>,
+    error_banner: ErrorBanner,
+}
+
+impl PropertyEditor {
+    pub fn new(error_banner: ErrorBanner) -> Self {
+        let title = gtk::Label::new(None);
+        title.set_css_classes(&["bold-title"]);
+        title.set_halign(gtk::Align::Start);
+        title.set_hexpand(true);
+        let reset_all_button = Button::with_label("Reset all");
+
+        let header_box = gtk::Box::new(Orientation::Horizontal, 5);
+        header_box.append(&title);
+        header_box.append(&reset_all_button);
+
+        let rows = gtk::Box::new(Orientation::Vertical, 5);
+        let rows_scroll = gtk::ScrolledWindow::builder()
+            .child(&rows)
+            .min_content_height(200)
+            .vexpand(true)
+            .build();
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.set_css_classes(&["property-editor"]);
+        container.append(&header_box);
+        container.append(&rows_scroll);
+
+        let editor = Self {
+            container,
+            title,
+            rows,
+            reset_all_button: reset_all_button.clone(),
+            element: Rc::new(RefCell::new(None)),
+            error_banner,
+        };
+        editor.show(None);
+
+        let editor_clone = editor.clone();
+        reset_all_button.connect_clicked(move |_| {
+            let Some(element) = editor_clone.element.borrow().clone() else {
+                return;
+            };
+            for pspec in editable_properties(&element) {
+                editor_clone.reset(&element, &pspec);
+            }
+            editor_clone.refresh();
+        });
+
+        editor
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    pub fn show(&self, stage: Option<&FilterStage>) {
+        self.element
+            .replace(stage.map(|stage| stage.element.clone()));
+        match stage {
+            Some(stage) => self.title.set_text(&format!("{} properties", stage.name)),
+            None => self.title.set_text("Select a filter to edit it"),
+        }
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        while let Some(child) = self.rows.first_child() {
+            self.rows.remove(&child);
+        }
+
+        let Some(element) = self.element.borrow().clone() else {
+            self.reset_all_button.set_sensitive(false);
+            return;
+        };
+
+        let properties = editable_properties(&element);
+        self.reset_all_button.set_sensitive(!properties.is_empty());
+        if properties.is_empty() {
+            let empty_label = gtk::Label::new(Some("No tunable properties"));
+            empty_label.set_halign(gtk::Align::Start);
+            self.rows.append(&empty_label);
+        }
+
+        for pspec in properties {
+            let Some(value_widget) = self.value_widget(&element, &pspec) else {
+                continue;
+            };
+
+            let name_label = gtk::Label::new(Some(pspec.nick()));
+            name_label.set_halign(gtk::Align::Start);
+            name_label.set_tooltip_text(pspec.blurb());
+
+            let reset_button = Button::with_label("↺");
+            reset_button.set_tooltip_text(Some("Reset to default"));
+            reset_button.set_valign(gtk::Align::Center);
+
+            let value_box = gtk::Box::new(Orientation::Horizontal, 5);
+            value_widget.set_hexpand(true);
+            value_box.append(&value_widget);
+            value_box.append(&reset_button);
+
+            let row_box = gtk::Box::new(Orientation::Vertical, 2);
+            row_box.append(&name_label);
+            row_box.append(&value_box);
+            self.rows.append(&row_box);
+
+            let editor_clone = self.clone();
+            let element_clone = element.clone();
+            reset_button.connect_clicked(move |_| {
+                editor_clone.reset(&element_clone, &pspec);
+                editor_clone.refresh();
+            });
+        }
+    }
+
+    // Resets go through the same checks as edits
+    fn reset(&self, element: &Element, pspec: &glib::ParamSpec) {
+        if let Err(err) = set_element_property(element, pspec.name(), pspec.default_value().clone())
+        {
+            self.error_banner.show(&err);
+        }
+    }
+
+    fn value_widget(&self, element: &Element, pspec: &glib::ParamSpec) -> Option<gtk::Widget> {
+        let value = element.property_value(pspec.name());
+        let name = pspec.name().to_string();
+        let element_name = element.name().to_string();
+        let element = element.clone();
+        let error_banner = self.error_banner.clone();
+        let apply = move |value: glib::Value| {
+            if let Err(err) = set_element_property(&element, &name, value) {
+                error_banner.show(&err);
+            }
+        };
+
+        if pspec.value_type() == glib::Type::BOOL {
+            let switch = gtk::Switch::new();
+            switch.set_halign(gtk::Align::Start);
+            switch.set_active(value.get::<bool>().unwrap_or_default());
+            switch.connect_active_notify(move |switch| apply(switch.is_active().to_value()));
+            return Some(switch.upcast());
+        }
+
+        if let Some(enum_pspec) = pspec.downcast_ref::<glib::ParamSpecEnum>() {
+            let enum_class = enum_pspec.enum_class();
+            let nicks: Vec<&str> = enum_class.values().iter().map(|v| v.nick()).collect();
+            let dropdown = gtk::DropDown::from_strings(&nicks);
+            if let Some((_, current)) = glib::EnumValue::from_value(&value) {
+                if let Some(position) = enum_class
+                    .values()
+                    .iter()
+                    .position(|v| v.value() == current.value())
+                {
+                    dropdown.set_selected(position as u32);
+                }
+            }
+            dropdown.connect_selected_notify(move |dropdown| {
+                let selected = enum_class
+                    .values()
+                    .get(dropdown.selected() as usize)
+                    .map(|v| v.to_value(&enum_class));
+                if let Some(selected) = selected {
+                    apply(selected);
+                }
+            });
+            return Some(dropdown.upcast());
+        }
+
+        if let Some(flags_pspec) = pspec.downcast_ref::<glib::ParamSpecFlags>() {
+            let flags_class = flags_pspec.flags_class();
+            let bits = Rc::new(Cell::new(
+                glib::FlagsValue::from_value(&value)
+                    .map(|(_, set)| set.iter().fold(0, |bits, flag| bits | flag.value()))
+                    .unwrap_or_default(),
+            ));
+            let apply = Rc::new(apply);
+            let flow_box = gtk::FlowBox::new();
+            flow_box.set_selection_mode(gtk::SelectionMode::None);
+            // A zero flag like "none" is just every box unticked
+            for flag in flags_class.values().iter().filter(|flag| flag.value() != 0) {
+                let check_button = gtk::CheckButton::with_label(flag.nick());
+                let flag_bits = flag.value();
+                check_button.set_active(bits.get() & flag_bits == flag_bits);
+                let flags_class = flags_class.clone();
+                let bits = bits.clone();
+                let apply = apply.clone();
+                check_button.connect_toggled(move |check_button| {
+                    if check_button.is_active() {
+                        bits.set(bits.get() | flag_bits);
+                    } else {
+                        bits.set(bits.get() & !flag_bits);
+                    }
+                    if let Some(value) = flags_class.to_value(bits.get()) {
+                        apply(value);
+                    }
+                });
+                flow_box.append(&check_button);
+            }
+            return Some(flow_box.upcast());
+        }
+
+        if pspec.value_type() == glib::Type::STRING {
+            let entry = gtk::Entry::new();
+            entry.set_text(
+                &value
+                    .get::<Option<String>>()
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default(),
+            );
+            entry.connect_activate(move |entry| apply(entry.text().to_value()));
+            return Some(entry.upcast());
+        }
+
+        if let Some((minimum, maximum)) = integer64_range(pspec) {
+            if minimum < -MAX_EXACT_F64 || maximum > MAX_EXACT_F64 {
+                let entry = gtk::Entry::new();
+                entry.set_input_purpose(gtk::InputPurpose::Number);
+                entry.set_text(&integer64(&value).map(|v| v.to_string()).unwrap_or_default());
+                let value_type = pspec.value_type();
+                let property = pspec.name().to_string();
+                let error_banner = self.error_banner.clone();
+                entry.connect_activate(move |entry| {
+                    match parse_integer64(&entry.text(), minimum, maximum, value_type) {
+                        Ok(value) => apply(value),
+                        Err(reason) => error_banner.show(&GstreamerError::Property {
+                            element: element_name.clone(),
+                            property: property.clone(),
+                            reason,
+                        }),
+                    }
+                });
+                return Some(entry.upcast());
+            }
+        }
+
+        let (minimum, maximum, digits) = numeric_range(pspec)?;
+        let current = value
+            .transform::<f64>()
+            .ok()
+            .and_then(|v| v.get::<f64>().ok())
+            .unwrap_or(minimum);
+        let value_type = pspec.value_type();
+        let apply_number = move |number: f64| {
+            // GLib converts between the numeric types, rounding as needed
+            match number.to_value().transform_with_type(value_type) {
+                Ok(value) => apply(value),
+                Err(err) => {
+                    glib::g_warning!(LOG_DOMAIN, "Can't convert {number} to {value_type}: {err}")
+                }
+            }
+        };
+
+        if digits > 0 && maximum - minimum <= MAX_SLIDER_RANGE {
+            let step = (maximum - minimum) / 100.0;
+            let scale = gtk::Scale::with_range(Orientation::Horizontal, minimum, maximum, step);
+            scale.set_digits(digits as i32);
+            scale.set_draw_value(true);
+            scale.set_value(current);
+            scale.connect_value_changed(move |scale| apply_number(scale.value()));
+            Some(scale.upcast())
+        } else {
+            let step = if digits > 0 { 0.1 } else { 1.0 };
+            let spin_button = gtk::SpinButton::with_range(minimum, maximum, step);
+            spin_button.set_digits(digits);
+            spin_button.set_value(current);
+            spin_button.connect_value_changed(move |spin_button| apply_number(spin_button.value()));
+            Some(spin_button.upcast())
+        }
+    }
+}
+
+// Bounds of 64-bit integer properties, widened so both signs fit
+fn integer64_range(pspec: &glib::ParamSpec) -> Option<(i128, i128)> {
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecInt64>() {
+        return Some((pspec.minimum().into(), pspec.maximum().into()));
+    }
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecUInt64>() {
+        return Some((pspec.minimum().into(), pspec.maximum().into()));
+    }
+    None
+}
+
+fn integer64(value: &glib::Value) -> Option<i128> {
+    value
+        .get::<i64>()
+        .map(i128::from)
+        .or_else(|_| value.get::<u64>().map(i128::from))
+        .ok()
+}
+
+// Parses without going through f64, so every 64-bit value round-trips
+fn parse_integer64(
+    text: &str,
+    minimum: i128,
+    maximum: i128,
+    value_type: glib::Type,
+) -> Result<glib::Value, String> {
+    let number: i128 = text
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a whole number", text.trim()))?;
+    if !(minimum..=maximum).contains(&number) {
+        return Err(format!("{number} is outside {minimum}..={maximum}"));
+    }
+    if value_type == glib::Type::U64 {
+        Ok(u64::try_from(number)
+            .map_err(|err| err.to_string())?
+            .to_value())
+    } else {
+        Ok(i64::try_from(number)
+            .map_err(|err| err.to_string())?
+            .to_value())
+    }
+}
+
+// Range and decimal places of numeric properties
+fn numeric_range(pspec: &glib::ParamSpec) -> Option<(f64, f64, u32)> {
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecInt>() {
+        return Some((pspec.minimum().into(), pspec.maximum().into(), 0));
+    }
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecUInt>() {
+        return Some((pspec.minimum().into(), pspec.maximum().into(), 0));
+    }
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecInt64>() {
+        return Some((pspec.minimum() as f64, pspec.maximum() as f64, 0));
+    }
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecUInt64>() {
+        return Some((pspec.minimum() as f64, pspec.maximum() as f64, 0));
+    }
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecFloat>() {
+        return Some((pspec.minimum().into(), pspec.maximum().into(), 2));
+    }
+    if let Some(pspec) = pspec.downcast_ref::<glib::ParamSpecDouble>() {
+        return Some((pspec.minimum(), pspec.maximum(), 2));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_integers_are_exact() {
+        let value = parse_integer64("18446744073709551615", 0, u64::MAX.into(), glib::Type::U64);
+        assert_eq!(value.unwrap().get::<u64>(), Ok(u64::MAX));
+
+        let beyond_f64 = (1_i64 << 53) + 1;
+        let value = parse_integer64(
+            &beyond_f64.to_string(),
+            i64::MIN.into(),
+            i64::MAX.into(),
+            glib::Type::I64,
+        );
+        assert_eq!(value.unwrap().get::<i64>(), Ok(beyond_f64));
+        assert_eq!(integer64(&beyond_f64.to_value()), Some(beyond_f64.into()));
+    }
+
+    #[test]
+    fn integers_outside_the_range_are_refused() {
+        assert!(parse_integer64("-1", 0, u64::MAX.into(), glib::Type::U64).is_err());
+        assert!(parse_integer64("11", 0, 10, glib::Type::I64).is_err());
+        assert!(parse_integer64("1.5", 0, 10, glib::Type::I64).is_err());
+        assert!(parse_integer64(" 7 ", 0, 10, glib::Type::I64).is_ok());
+    }
+}
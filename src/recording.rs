@@ -0,0 +1,231 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use chrono::Local;
+use gst::{prelude::*, Element, PadProbeReturn, PadProbeType};
+
+use crate::{
+    encoding::{Container, EncodingBranch, EncodingOptions},
+    error::GstreamerError,
+    LOG_DOMAIN,
+};
+
+pub const RECORDING_FINISHED: &str = "recording-finished";
+
+type TeeLink = (Element, gst::Pad, gst::Pad);
+
+// Encoding branch hanging off the video and audio tees while recording.
+// Flushing seeks would flush through the tees into the muxer and break the
+// file, so the manager refuses them until the branch has ended, also while
+// it is still being stopped.
+pub struct Recording {
+    // Emptied by `stop`
+    links: Vec<TeeLink>,
+    // The muxer got EOS, the stream ended and the file is complete
+    ended: Arc<AtomicBool>,
+}
+
+impl Recording {
+    pub fn start(
+        pipeline: &gst::Pipeline,
+        video_tee: &Element,
+        audio_tee: &Element,
+        has_audio: bool,
+        options: &EncodingOptions,
+        location: &str,
+    ) -> Result<Self, GstreamerError> {
+        let bin = gst::Bin::with_name("recording-bin");
+        let branch = EncodingBranch::new_live(&bin, options, location)?;
+        let video_pad = add_named_ghost_pad(&bin, &branch.video_entry, "video_sink")?;
+        let audio_pad = add_named_ghost_pad(&bin, &branch.audio_entry, "audio_sink")?;
+
+        // Make the file start at zero instead of the current running time
+        if let Some(running_time) = pipeline.current_running_time() {
+            let offset = -(running_time.nseconds() as i64);
+            video_pad.set_offset(offset);
+            audio_pad.set_offset(offset);
+        }
+
+        let ended = Arc::new(AtomicBool::new(false));
+        let ended_clone = ended.clone();
+        let pipeline_weak = pipeline.downgrade();
+        let bin_weak = bin.downgrade();
+        let location_clone = location.to_string();
+        let file_sink_pad = branch
+            .file_sink
+            .static_pad("sink")
+            .ok_or_else(|| glib::bool_error!("filesink has no sink pad"))?;
+        file_sink_pad.add_probe(PadProbeType::EVENT_DOWNSTREAM, move |_pad, info| {
+            let is_eos = info
+                .event()
+                .is_some_and(|event| event.type_() == gst::EventType::Eos);
+            if !is_eos {
+                return PadProbeReturn::Ok;
+            }
+            ended_clone.store(true, Ordering::SeqCst);
+
+            // The muxer has written everything, finish up from the main loop
+            let pipeline_weak = pipeline_weak.clone();
+            let bin_weak = bin_weak.clone();
+            let location = location_clone.clone();
+            glib::idle_add_once(move || {
+                let (Some(pipeline), Some(bin)) = (pipeline_weak.upgrade(), bin_weak.upgrade())
+                else {
+                    return;
+                };
+                bin.set_state(gst::State::Null).ok();
+                pipeline.remove(&bin).ok();
+
+                let structure = gst::Structure::builder(RECORDING_FINISHED)
+                    .field("location", location)
+                    .build();
+                pipeline
+                    .post_message(gst::message::Application::new(structure))
+                    .ok();
+            });
+            PadProbeReturn::Remove
+        });
+
+        pipeline.add(&bin)?;
+        let mut links = Vec::new();
+        let attached = bin
+            .sync_state_with_parent()
+            .map_err(GstreamerError::from)
+            .and_then(|_| {
+                attach_to_tees(
+                    [
+                        (video_tee, &video_pad, "video", true),
+                        (audio_tee, &audio_pad, "audio", has_audio),
+                    ],
+                    &mut links,
+                )
+            });
+        // Don't leave a running branch that only gets half of the streams
+        if let Err(err) = attached {
+            for (tee, tee_pad, sink_pad) in links {
+                tee_pad.unlink(&sink_pad).ok();
+                tee.release_request_pad(&tee_pad);
+            }
+            bin.set_state(gst::State::Null).ok();
+            pipeline.remove(&bin).ok();
+            return Err(err);
+        }
+
+        glib::g_info!(LOG_DOMAIN, "Recording to '{location}'");
+        Ok(Self { links, ended })
+    }
+
+    pub fn has_ended(&self) -> bool {
+        self.ended.load(Ordering::SeqCst)
+    }
+
+    // Stopped, but the EOS hasn't reached the file yet
+    pub fn is_stopping(&self) -> bool {
+        self.links.is_empty() && !self.has_ended()
+    }
+
+    // Detaches from the tees and pushes EOS through the encoders so the
+    // muxer can finalize the file; the branch removes itself afterwards
+    pub fn stop(&mut self) {
+        for (tee, tee_pad, sink_pad) in std::mem::take(&mut self.links) {
+            tee_pad.add_probe(PadProbeType::IDLE, move |tee_pad, _info| {
+                tee_pad.unlink(&sink_pad).ok();
+                sink_pad.send_event(gst::event::Eos::new());
+                tee.release_request_pad(tee_pad);
+                PadProbeReturn::Remove
+            });
+        }
+    }
+}
+
+// Links every stream to its tee, `links` holds the ones that are linked
+fn attach_to_tees(
+    streams: [(&Element, &gst::Pad, &str, bool); 2],
+    links: &mut Vec<TeeLink>,
+) -> Result<(), GstreamerError> {
+    for (tee, sink_pad, name, has_stream) in streams {
+        // Nothing will ever arrive, let the muxer go on without it
+        if !has_stream {
+            sink_pad.send_event(gst::event::Eos::new());
+            continue;
+        }
+        let tee_pad = tee
+            .request_pad_simple("src_%u")
+            .ok_or_else(|| glib::bool_error!("Failed to request a pad from {}", tee.name()))?;
+        if let Err(err) = tee_pad.link(sink_pad) {
+            tee.release_request_pad(&tee_pad);
+            return Err(GstreamerError::Link(format!(
+                "{name} tee → recording: {err:?}"
+            )));
+        }
+        links.push((tee.clone(), tee_pad, sink_pad.clone()));
+    }
+    Ok(())
+}
+
+pub fn default_location(container: Container) -> String {
+    let folder = dirs::video_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let name = format!(
+        "recording-{}.{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        container.extension()
+    );
+    folder.join(name).to_string_lossy().to_string()
+}
+
+fn add_named_ghost_pad(
+    bin: &gst::Bin,
+    element: &Element,
+    name: &str,
+) -> Result<gst::Pad, GstreamerError> {
+    let pad = element
+        .static_pad("sink")
+        .ok_or_else(|| glib::bool_error!("{} has no sink pad", element.name()))?;
+    let ghost_pad = gst::GhostPad::builder_with_target(&pad)?.name(name).build();
+    bin.add_pad(&ghost_pad)?;
+    Ok(ghost_pad.upcast())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_media;
+
+    #[test]
+    fn failed_start_leaves_the_pipeline_as_it_was() {
+        test_media::init();
+        let pipeline = gst::parse::launch(
+            "videotestsrc is-live=true ! tee name=video_tee allow-not-linked=true ! fakesink \
+             audiotestsrc is-live=true ! identity name=not_a_tee ! fakesink",
+        )
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+        let video_tee = pipeline.by_name("video_tee").unwrap();
+        let not_a_tee = pipeline.by_name("not_a_tee").unwrap();
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // The video link works, then the audio pad can't be requested
+        let location = test_media::temp_path("failed-recording.webm");
+        let result = Recording::start(
+            &pipeline,
+            &video_tee,
+            &not_a_tee,
+            true,
+            &EncodingOptions::for_container(Container::Webm),
+            location.to_string_lossy().as_ref(),
+        );
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        assert!(result.is_err());
+        assert!(pipeline.by_name("recording-bin").is_none());
+        assert_eq!(video_tee.src_pads().len(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{cell::RefCell, io::Write, rc::Rc, time::Duration};
+
+use anyhow::{anyhow, Context};
+use gst::prelude::*;
+
+use crate::{bus::PlayerEvent, encoding::EncodingOptions, gstreamer::GstreamerManager};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub input: String,
+    pub output: String,
+    pub filters: Vec<String>,
+    pub encoding: EncodingOptions,
+}
+
+pub fn run(options: &RenderOptions) -> anyhow::Result<()> {
+    gst::init().context("Failed to init GStreamer")?;
+
+    let mut gst_manager = GstreamerManager::new_render(&options.encoding, &options.output)?;
+    gst_manager.create_pipeline(&options.input)?;
+    // Nothing flows yet, so the filters are linked before this returns
+    let relinked = Rc::new(RefCell::new(None));
+    let relinked_clone = relinked.clone();
+    gst_manager.set_filters(&options.filters, move |result| {
+        relinked_clone.replace(Some(result));
+    })?;
+    relinked
+        .take()
+        .ok_or_else(|| anyhow!("The filters were not linked"))??;
+
+    println!(
+        "Rendering '{}' → '{}' ({:?})",
+        options.input, options.output, options.encoding
+    );
+
+    let main_loop = glib::MainLoop::new(None, false);
+    let failure: Rc<RefCell<Option<anyhow::Error>>> = Rc::new(RefCell::new(None));
+
+    let main_loop_clone = main_loop.clone();
+    let failure_clone = failure.clone();
+    gst_manager.bus.subscribe(move |event| match event {
+        PlayerEvent::Eos => main_loop_clone.quit(),
+        PlayerEvent::Error {
+            source,
+            message,
+            debug,
+        } => {
+            let mut err = anyhow!("{source}: {message}");
+            if let Some(debug) = debug {
+                err = err.context(debug.clone());
+            }
+            failure_clone.replace(Some(err));
+            main_loop_clone.quit();
+        }
+        _ => {}
+    });
+
+    let pipeline_clone = gst_manager.pipeline.clone();
+    let progress_source = glib::timeout_add_local(PROGRESS_INTERVAL, move || {
+        if let (Some(position), Some(duration)) = (
+            pipeline_clone.query_position::<gst::ClockTime>(),
+            pipeline_clone.query_duration::<gst::ClockTime>(),
+        ) {
+            print_progress(position, duration);
+        }
+        glib::ControlFlow::Continue
+    });
+
+    gst_manager
+        .pipeline
+        .set_state(gst::State::Playing)
+        .context("Failed to start rendering")?;
+    main_loop.run();
+
+    progress_source.remove();
+    gst_manager.pipeline.set_state(gst::State::Null).ok();
+    println!();
+
+    match failure.take() {
+        Some(err) => Err(err),
+        None => {
+            println!("Saved '{}'", options.output);
+            Ok(())
+        }
+    }
+}
+
+fn print_progress(position: gst::ClockTime, duration: gst::ClockTime) {
+    let percent = if duration.is_zero() {
+        0.0
+    } else {
+        position.nseconds() as f64 * 100.0 / duration.nseconds() as f64
+    };
+    let format_time = |t: u64| format!("{:02}:{:02}", t / 60, t % 60);
+
+    print!(
+        "\r{}Rendering {} / {} ({percent:5.1}%)",
+        termion::clear::CurrentLine,
+        format_time(position.seconds()),
+        format_time(duration.seconds())
+    );
+    std::io::stdout().flush().ok();
+}
@@ -0,0 +1,71 @@
+use std::{str::FromStr, time::Duration};
+
+pub const SEEK_MODE_NAMES: &[&str] = &["Scrub", "Accurate", "Keyframe"];
+
+// Minimum time between two seeks while scrubbing
+pub const SCRUB_INTERVAL: Duration = Duration::from_millis(100);
+// Seeks while scrubbing land on the closest keyframe, which is fast to decode
+pub const SCRUB_FLAGS: gst::SeekFlags =
+    gst::SeekFlags::KEY_UNIT.union(gst::SeekFlags::SNAP_NEAREST);
+
+// How the progress slider seeks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SeekMode {
+    // Keyframe seeks while dragging for a live preview, accurate on release
+    #[default]
+    Scrub,
+    // Accurate seek on release only
+    Accurate,
+    // Nearest keyframe on release, the fastest
+    Keyframe,
+}
+
+impl SeekMode {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => SeekMode::Accurate,
+            2 => SeekMode::Keyframe,
+            _ => SeekMode::Scrub,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        match self {
+            SeekMode::Scrub => 0,
+            SeekMode::Accurate => 1,
+            SeekMode::Keyframe => 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeekMode::Scrub => "scrub",
+            SeekMode::Accurate => "accurate",
+            SeekMode::Keyframe => "keyframe",
+        }
+    }
+
+    pub fn scrubs(&self) -> bool {
+        *self == SeekMode::Scrub
+    }
+
+    pub fn release_flags(&self) -> gst::SeekFlags {
+        match self {
+            SeekMode::Scrub | SeekMode::Accurate => gst::SeekFlags::ACCURATE,
+            SeekMode::Keyframe => gst::SeekFlags::KEY_UNIT,
+        }
+    }
+}
+
+impl FromStr for SeekMode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "scrub" => Ok(SeekMode::Scrub),
+            "accurate" => Ok(SeekMode::Accurate),
+            "keyframe" => Ok(SeekMode::Keyframe),
+            _ => Err(format!("Unknown seek mode '{name}'")),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use gst_video::prelude::*;
+use gtk::{prelude::*, ApplicationWindow, Button, Orientation};
+
+use crate::{
+    bus::PlayerEvent,
+    error_banner::ErrorBanner,
+    gstreamer::GstreamerManager,
+    seek::{SeekMode, SCRUB_FLAGS, SCRUB_INTERVAL, SEEK_MODE_NAMES},
+    settings::Settings,
+    window::add_action,
+};
+
+// Playtime, frame number, the progress bar with its seek mode and the A-B loop
+pub struct SeekBar {
+    container: gtk::Box,
+}
+
+impl SeekBar {
+    pub fn new(
+        window: &ApplicationWindow,
+        settings: Rc<RefCell<Settings>>,
+        gst_manager: &GstreamerManager,
+        error_banner: ErrorBanner,
+    ) -> Self {
+        let progress_slider = gtk::Scale::with_range(Orientation::Horizontal, 0.0, 100.0, 1.0);
+        progress_slider.set_hexpand(true);
+        progress_slider.set_valign(gtk::Align::Center);
+
+        let playtime_label = gtk::Label::new(None);
+        playtime_label.set_css_classes(&["time"]);
+        playtime_label.set_halign(gtk::Align::End);
+        playtime_label.set_valign(gtk::Align::Center);
+
+        let frame_label = gtk::Label::new(None);
+        frame_label.set_css_classes(&["time"]);
+        frame_label.set_valign(gtk::Align::Center);
+        frame_label.set_tooltip_text(Some("Step frames with , and ."));
+
+        let seek_mode_selector = gtk::DropDown::from_strings(SEEK_MODE_NAMES);
+        seek_mode_selector.set_valign(gtk::Align::Center);
+        seek_mode_selector.set_tooltip_text(Some("How the progress bar seeks"));
+
+        let ab_loop_button = Button::with_label("A-B loop");
+        ab_loop_button.set_valign(gtk::Align::Center);
+        ab_loop_button.set_tooltip_text(Some("Set A, then B, then clear (l)"));
+
+        let container = gtk::Box::new(Orientation::Horizontal, 5);
+        container.append(&playtime_label);
+        container.append(&frame_label);
+        container.append(&progress_slider);
+        container.append(&seek_mode_selector);
+        container.append(&ab_loop_button);
+
+        // Set while the user drags the slider, which then isn't moved along
+        let is_dragging = Rc::new(Cell::new(false));
+
+        load_slider_movement(progress_slider.clone(), is_dragging.clone(), gst_manager);
+        load_playtime_indicator(playtime_label, gst_manager);
+        load_frame_indicator(frame_label, gst_manager);
+        load_ab_loop_logic(
+            window,
+            ab_loop_button,
+            progress_slider.clone(),
+            gst_manager,
+            error_banner.clone(),
+        );
+        load_slider_interaction(
+            window,
+            progress_slider,
+            seek_mode_selector,
+            is_dragging,
+            settings,
+            gst_manager,
+            error_banner,
+        );
+
+        Self { container }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+}
+
+fn load_slider_movement(
+    progress_slider: gtk::Scale,
+    is_dragging: Rc<Cell<bool>>,
+    gst_manager: &GstreamerManager,
+) {
+    let pipeline_clone = gst_manager.pipeline.clone();
+    let progress_slider_clone = progress_slider.clone();
+    let is_dragging_clone = is_dragging.clone();
+
+    glib::timeout_add_local(Duration::from_millis(50), move || {
+        // Leave the slider where the user is dragging it
+        if is_dragging_clone.get() {
+            return glib::ControlFlow::Continue;
+        }
+        if let (Some(position), Some(duration)) = (
+            pipeline_clone.query_position::<gst::ClockTime>(),
+            pipeline_clone.query_duration::<gst::ClockTime>(),
+        ) {
+            let pos_ns = position.nseconds();
+            let dur_ns = duration.nseconds();
+
+            let pos_secs = pos_ns as f64 / 1_000_000_000.0;
+            let dur_secs = dur_ns as f64 / 1_000_000_000.0;
+
+            progress_slider_clone.set_range(0.0, dur_secs);
+            progress_slider_clone.set_value(pos_secs);
+        }
+
+        glib::ControlFlow::Continue
+    });
+}
+
+fn load_playtime_indicator(playtime_label: gtk::Label, gst_manager: &GstreamerManager) {
+    let pipeline_clone = gst_manager.pipeline.clone();
+    let playtime_label_clone = playtime_label.clone();
+
+    glib::timeout_add_local(Duration::from_millis(50), move || {
+        if let (Some(position), Some(duration)) = (
+            pipeline_clone.query_position::<gst::ClockTime>(),
+            pipeline_clone.query_duration::<gst::ClockTime>(),
+        ) {
+            let pos_secs = position.seconds();
+            let dur_secs = duration.seconds();
+
+            let format_time = |t: u64| format!("{:02}:{:02}", t / 60, t % 60);
+            let text = format!("{} / {}", format_time(pos_secs), format_time(dur_secs));
+
+            playtime_label_clone.set_text(&text);
+        }
+
+        glib::ControlFlow::Continue
+    });
+}
+
+fn load_frame_indicator(frame_label: gtk::Label, gst_manager: &GstreamerManager) {
+    let gst_manager_clone = gst_manager.clone();
+    glib::timeout_add_local(Duration::from_millis(50), move || {
+        match gst_manager_clone.frame_number() {
+            Some(frame) => frame_label.set_text(&format!("frame {frame}")),
+            None => frame_label.set_text(""),
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+// Like mpv: the first press sets A, the second sets B and starts the loop,
+// the third clears it
+fn load_ab_loop_logic(
+    window: &ApplicationWindow,
+    ab_loop_button: gtk::Button,
+    progress_slider: gtk::Scale,
+    gst_manager: &GstreamerManager,
+    error_banner: ErrorBanner,
+) {
+    let marker_a: Rc<Cell<Option<gst::ClockTime>>> = Rc::new(Cell::new(None));
+
+    let gst_manager_clone = gst_manager.clone();
+    let marker_a_clone = marker_a.clone();
+    let ab_loop_button_clone = ab_loop_button.clone();
+    let refresh = Rc::new(move || {
+        let as_seconds = |time: gst::ClockTime| time.nseconds() as f64 / 1_000_000_000.0;
+        progress_slider.clear_marks();
+        if let Some((a, b)) = gst_manager_clone.ab_loop() {
+            progress_slider.add_mark(as_seconds(a), gtk::PositionType::Bottom, Some("A"));
+            progress_slider.add_mark(as_seconds(b), gtk::PositionType::Bottom, Some("B"));
+            ab_loop_button_clone.set_label("Clear A-B");
+        } else if let Some(a) = marker_a_clone.get() {
+            progress_slider.add_mark(as_seconds(a), gtk::PositionType::Bottom, Some("A"));
+            ab_loop_button_clone.set_label("Set B");
+        } else {
+            ab_loop_button_clone.set_label("A-B loop");
+        }
+    });
+
+    let gst_manager_clone = gst_manager.clone();
+    let error_banner_clone = error_banner.clone();
+    let marker_a_clone = marker_a.clone();
+    let refresh_clone = refresh.clone();
+    let cycle = Rc::new(move || {
+        let result = if gst_manager_clone.ab_loop().is_some() {
+            gst_manager_clone.clear_ab_loop()
+        } else if let Some(position) = gst_manager_clone
+            .pipeline
+            .query_position::<gst::ClockTime>()
+        {
+            match marker_a_clone.take() {
+                Some(a) => gst_manager_clone.set_ab_loop(a, position),
+                None => {
+                    marker_a_clone.set(Some(position));
+                    Ok(())
+                }
+            }
+        } else {
+            Ok(())
+        };
+        if let Err(err) = result {
+            error_banner_clone.show(&err);
+        }
+        refresh_clone();
+    });
+
+    add_action(window, "ab-loop", move || cycle());
+    ab_loop_button.set_action_name(Some("win.ab-loop"));
+
+    // Seamless looping: each finished segment queues the next one. A new
+    // file drops the markers of the previous one.
+    let gst_manager_clone = gst_manager.clone();
+    let error_banner_clone = error_banner.clone();
+    gst_manager.bus.subscribe(move |event| match event {
+        PlayerEvent::SegmentDone => {
+            if let Err(err) = gst_manager_clone.repeat_ab_loop() {
+                error_banner_clone.show(&err);
+            }
+        }
+        PlayerEvent::StreamStart => {
+            marker_a.set(None);
+            refresh();
+        }
+        _ => {}
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn load_slider_interaction(
+    window: &ApplicationWindow,
+    progress_slider: gtk::Scale,
+    seek_mode_selector: gtk::DropDown,
+    is_dragging: Rc<Cell<bool>>,
+    settings: Rc<RefCell<Settings>>,
+    gst_manager: &GstreamerManager,
+    error_banner: ErrorBanner,
+) {
+    let settings_clone = settings.clone();
+    seek_mode_selector.set_selected(settings_clone.borrow().seek_mode.index());
+    seek_mode_selector.connect_selected_notify(move |seek_mode_selector| {
+        settings_clone.borrow_mut().seek_mode = SeekMode::from_index(seek_mode_selector.selected());
+    });
+
+    let gst_manager_clone = gst_manager.clone();
+    let error_banner_clone = error_banner.clone();
+    let seek_to = move |seconds: f64, flags: gst::SeekFlags| {
+        let position = gst::ClockTime::from_nseconds((seconds.max(0.0) * 1_000_000_000.0) as u64);
+        // Goes through the manager so the playback speed is kept
+        if let Err(err) = gst_manager_clone.seek(position, flags) {
+            error_banner_clone.show(&err);
+        }
+    };
+    let seek_to = Rc::new(seek_to);
+
+    for (name, offset) in [
+        ("seek-backward", -5.0),
+        ("seek-forward", 5.0),
+        ("seek-backward-long", -30.0),
+        ("seek-forward-long", 30.0),
+    ] {
+        let progress_slider_clone = progress_slider.clone();
+        let settings_clone = settings.clone();
+        let seek_to_clone = seek_to.clone();
+        add_action(window, name, move || {
+            let maximum = progress_slider_clone.adjustment().upper();
+            let target = (progress_slider_clone.value() + offset).min(maximum);
+            seek_to_clone(target, settings_clone.borrow().seek_mode.release_flags());
+        });
+    }
+
+    // Keyboard, scroll and the drag itself all end up here. While
+    // dragging, scrub mode previews with throttled keyframe seeks.
+    let is_dragging_clone = is_dragging.clone();
+    let settings_clone = settings.clone();
+    let seek_to_clone = seek_to.clone();
+    let last_scrub: Rc<Cell<Option<Instant>>> = Rc::new(Cell::new(None));
+    progress_slider.connect_change_value(move |_, _, value| {
+        let seek_mode = settings_clone.borrow().seek_mode;
+        if !is_dragging_clone.get() {
+            seek_to_clone(value, seek_mode.release_flags());
+        } else if seek_mode.scrubs()
+            && last_scrub
+                .get()
+                .is_none_or(|last| last.elapsed() >= SCRUB_INTERVAL)
+        {
+            last_scrub.set(Some(Instant::now()));
+            seek_to_clone(value, SCRUB_FLAGS);
+        }
+        glib::Propagation::Proceed
+    });
+
+    // Clicking the trough jumps there instead of paging towards it. This
+    // runs before the drag below starts, which seeks on release.
+    let click = gtk::GestureClick::new();
+    click.set_propagation_phase(gtk::PropagationPhase::Capture);
+    let progress_slider_clone = progress_slider.clone();
+    click.connect_pressed(move |_, _, x, _| {
+        progress_slider_clone.set_value(trough_value(&progress_slider_clone, x));
+    });
+    progress_slider.add_controller(click);
+
+    let gesture = gtk::GestureDrag::new();
+    progress_slider.add_controller(gesture.clone());
+
+    let is_dragging_clone = is_dragging.clone();
+    gesture.connect_drag_begin(move |_, _, _| {
+        is_dragging_clone.set(true);
+    });
+
+    let is_dragging_clone = is_dragging.clone();
+    let settings_clone = settings.clone();
+    gesture.connect_drag_end(move |_, _, _| {
+        is_dragging_clone.set(false);
+        seek_to(
+            progress_slider.value(),
+            settings_clone.borrow().seek_mode.release_flags(),
+        );
+    });
+}
+
+// The value under `x`, with the middle of the knob landing on the pointer
+fn trough_value(scale: &gtk::Scale, x: f64) -> f64 {
+    let trough = scale.range_rect();
+    let (knob_start, knob_end) = scale.slider_range();
+    let knob = f64::from(knob_end - knob_start);
+    let travel = (f64::from(trough.width()) - knob).max(1.0);
+    let mut fraction = ((x - f64::from(trough.x()) - knob / 2.0) / travel).clamp(0.0, 1.0);
+    if scale.is_inverted() != (scale.direction() == gtk::TextDirection::Rtl) {
+        fraction = 1.0 - fraction;
+    }
+    let adjustment = scale.adjustment();
+    adjustment.lower() + fraction * (adjustment.upper() - adjustment.lower())
+}
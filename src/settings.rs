@@ -0,0 +1,143 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use toml::{Table, Value};
+
+use crate::{
+    config::{config_file, read_table, write_table},
+    error::GstreamerError,
+    seek::SeekMode,
+    subtitles::{SubtitlePosition, DEFAULT_FONT_SIZE, MAX_FONT_SIZE, MIN_FONT_SIZE},
+    volume::VolumeScale,
+};
+
+const SETTINGS_FILE: &str = "settings.toml";
+
+// User preferences remembered across sessions
+#[derive(Debug, Clone)]
+pub struct Settings {
+    path: Option<PathBuf>,
+    // Linear volume, independent of the scale the slider shows
+    pub volume: f64,
+    pub muted: bool,
+    pub volume_scale: VolumeScale,
+    pub seek_mode: SeekMode,
+    // In points
+    pub subtitle_font_size: u32,
+    pub subtitle_position: SubtitlePosition,
+    // Remapped keys per action, e.g. `play-pause = ["space", "k"]`
+    pub shortcuts: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            volume: 1.0,
+            muted: false,
+            volume_scale: VolumeScale::default(),
+            seek_mode: SeekMode::default(),
+            subtitle_font_size: DEFAULT_FONT_SIZE,
+            subtitle_position: SubtitlePosition::default(),
+            shortcuts: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    // Missing or unreadable values fall back to their defaults
+    pub fn load() -> Result<Self, GstreamerError> {
+        let path = config_file(SETTINGS_FILE)?;
+        let table = read_table(&path)?;
+        let defaults = Self::default();
+
+        Ok(Self {
+            volume: table
+                .get("volume")
+                .and_then(Value::as_float)
+                .map_or(defaults.volume, |volume| volume.clamp(0.0, 1.0)),
+            muted: table
+                .get("muted")
+                .and_then(Value::as_bool)
+                .unwrap_or(defaults.muted),
+            volume_scale: table
+                .get("volume_scale")
+                .and_then(Value::as_str)
+                .and_then(|name| name.parse().ok())
+                .unwrap_or(defaults.volume_scale),
+            seek_mode: table
+                .get("seek_mode")
+                .and_then(Value::as_str)
+                .and_then(|name| name.parse().ok())
+                .unwrap_or(defaults.seek_mode),
+            subtitle_font_size: table
+                .get("subtitle_font_size")
+                .and_then(Value::as_integer)
+                .and_then(|size| u32::try_from(size).ok())
+                .map_or(defaults.subtitle_font_size, |size| {
+                    size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE)
+                }),
+            subtitle_position: table
+                .get("subtitle_position")
+                .and_then(Value::as_str)
+                .and_then(|name| name.parse().ok())
+                .unwrap_or(defaults.subtitle_position),
+            shortcuts: table
+                .get("shortcuts")
+                .and_then(Value::as_table)
+                .map(|shortcuts| {
+                    shortcuts
+                        .iter()
+                        .filter_map(|(action, accels)| {
+                            let accels = accels
+                                .as_array()?
+                                .iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect();
+                            Some((action.clone(), accels))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            path: Some(path),
+        })
+    }
+
+    pub fn save(&self) -> Result<(), GstreamerError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut table = Table::new();
+        table.insert("volume".to_string(), Value::Float(self.volume));
+        table.insert("muted".to_string(), Value::Boolean(self.muted));
+        table.insert(
+            "volume_scale".to_string(),
+            Value::String(self.volume_scale.name().to_string()),
+        );
+        table.insert(
+            "seek_mode".to_string(),
+            Value::String(self.seek_mode.name().to_string()),
+        );
+        table.insert(
+            "subtitle_font_size".to_string(),
+            Value::Integer(self.subtitle_font_size.into()),
+        );
+        table.insert(
+            "subtitle_position".to_string(),
+            Value::String(self.subtitle_position.name().to_string()),
+        );
+        if !self.shortcuts.is_empty() {
+            let shortcuts: Table = self
+                .shortcuts
+                .iter()
+                .map(|(action, accels)| {
+                    let accels = accels.iter().cloned().map(Value::String).collect();
+                    (action.clone(), Value::Array(accels))
+                })
+                .collect();
+            table.insert("shortcuts".to_string(), Value::Table(shortcuts));
+        }
+        write_table(path, &table)
+    }
+}
@@ -0,0 +1,481 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use gtk::prelude::*;
+
+// A `win.` action with its default keys, in GTK accelerator syntax
+pub struct Shortcut {
+    pub action: &'static str,
+    pub title: &'static str,
+    pub group: &'static str,
+    pub accels: &'static [&'static str],
+}
+
+pub const SHORTCUT_GROUPS: &[&str] = &["Playback", "Seeking", "Audio", "Video", "Application"];
+
+pub const SHORTCUTS: &[Shortcut] = &[
+    Shortcut {
+        action: "play-pause",
+        title: "Play or pause",
+        group: "Playback",
+        accels: &["space", "k"],
+    },
+    Shortcut {
+        action: "stop",
+        title: "Stop",
+        group: "Playback",
+        accels: &["s"],
+    },
+    Shortcut {
+        action: "previous",
+        title: "Previous in playlist",
+        group: "Playback",
+        accels: &["p"],
+    },
+    Shortcut {
+        action: "next",
+        title: "Next in playlist",
+        group: "Playback",
+        accels: &["n"],
+    },
+    Shortcut {
+        action: "speed-down",
+        title: "Slower",
+        group: "Playback",
+        accels: &["bracketleft"],
+    },
+    Shortcut {
+        action: "speed-up",
+        title: "Faster",
+        group: "Playback",
+        accels: &["bracketright"],
+    },
+    Shortcut {
+        action: "speed-reset",
+        title: "Normal speed",
+        group: "Playback",
+        accels: &["BackSpace"],
+    },
+    Shortcut {
+        action: "seek-backward",
+        title: "Back 5 seconds",
+        group: "Seeking",
+        accels: &["Left"],
+    },
+    Shortcut {
+        action: "seek-forward",
+        title: "Forward 5 seconds",
+        group: "Seeking",
+        accels: &["Right"],
+    },
+    Shortcut {
+        action: "seek-backward-long",
+        title: "Back 30 seconds",
+        group: "Seeking",
+        accels: &["<Shift>Left"],
+    },
+    Shortcut {
+        action: "seek-forward-long",
+        title: "Forward 30 seconds",
+        group: "Seeking",
+        accels: &["<Shift>Right"],
+    },
+    Shortcut {
+        action: "step-backward",
+        title: "Previous frame",
+        group: "Seeking",
+        accels: &["comma"],
+    },
+    Shortcut {
+        action: "step-forward",
+        title: "Next frame",
+        group: "Seeking",
+        accels: &["period"],
+    },
+    Shortcut {
+        action: "ab-loop",
+        title: "Set A, set B, clear the loop",
+        group: "Seeking",
+        accels: &["l"],
+    },
+    Shortcut {
+        action: "volume-up",
+        title: "Volume up",
+        group: "Audio",
+        accels: &["Up"],
+    },
+    Shortcut {
+        action: "volume-down",
+        title: "Volume down",
+        group: "Audio",
+        accels: &["Down"],
+    },
+    Shortcut {
+        action: "mute",
+        title: "Mute",
+        group: "Audio",
+        accels: &["m"],
+    },
+    Shortcut {
+        action: "next-audio-track",
+        title: "Next audio track",
+        group: "Audio",
+        accels: &["a"],
+    },
+    Shortcut {
+        action: "fullscreen",
+        title: "Fullscreen",
+        group: "Video",
+        accels: &["f", "F11"],
+    },
+    Shortcut {
+        action: "leave-fullscreen",
+        title: "Leave fullscreen",
+        group: "Video",
+        accels: &["Escape"],
+    },
+    Shortcut {
+        action: "next-subtitle-track",
+        title: "Next subtitle track or off",
+        group: "Video",
+        accels: &["c"],
+    },
+    Shortcut {
+        action: "subtitles-earlier",
+        title: "Show subtitles 0.1 s earlier",
+        group: "Video",
+        accels: &["z"],
+    },
+    Shortcut {
+        action: "subtitles-later",
+        title: "Show subtitles 0.1 s later",
+        group: "Video",
+        accels: &["x"],
+    },
+    Shortcut {
+        action: "rotate",
+        title: "Rotate 90° clockwise",
+        group: "Video",
+        accels: &["r"],
+    },
+    Shortcut {
+        action: "mirror",
+        title: "Mirror",
+        group: "Video",
+        accels: &["h"],
+    },
+    Shortcut {
+        action: "snapshot",
+        title: "Snapshot",
+        group: "Video",
+        accels: &["<Control>s"],
+    },
+    Shortcut {
+        action: "record",
+        title: "Start or stop recording",
+        group: "Video",
+        accels: &["<Control>r"],
+    },
+    Shortcut {
+        action: "filter-panel",
+        title: "Show or hide the filters",
+        group: "Video",
+        accels: &["F9"],
+    },
+    Shortcut {
+        action: "clear-filters",
+        title: "Clear filters",
+        group: "Video",
+        accels: &["<Control>BackSpace"],
+    },
+    Shortcut {
+        action: "video-info",
+        title: "Video info",
+        group: "Video",
+        accels: &["i"],
+    },
+    Shortcut {
+        action: "shortcuts",
+        title: "Keyboard shortcuts",
+        group: "Application",
+        accels: &["question", "F1"],
+    },
+    Shortcut {
+        action: "quit",
+        title: "Quit",
+        group: "Application",
+        accels: &["<Control>q"],
+    },
+];
+
+// Keys of every action, with the user's remapped ones from the settings.
+// Keys that can't be parsed or are already taken by an earlier action are
+// left out and reported in `problems`.
+pub struct KeyBindings {
+    bindings: Vec<(&'static Shortcut, Vec<String>)>,
+    problems: Vec<String>,
+}
+
+impl KeyBindings {
+    pub fn new(overrides: &BTreeMap<String, Vec<String>>) -> Self {
+        let mut problems = Vec::new();
+        for action in overrides.keys() {
+            if !SHORTCUTS.iter().any(|shortcut| shortcut.action == action) {
+                problems.push(format!("Keys set for unknown action '{action}'"));
+            }
+        }
+
+        let mut taken: BTreeMap<String, &str> = BTreeMap::new();
+        let mut bindings = Vec::new();
+        for shortcut in SHORTCUTS {
+            let accels: Vec<String> = match overrides.get(shortcut.action) {
+                Some(accels) => accels.clone(),
+                None => shortcut
+                    .accels
+                    .iter()
+                    .map(|accel| accel.to_string())
+                    .collect(),
+            };
+
+            let mut kept = Vec::new();
+            for accel in accels {
+                // One name for all spellings of a combination, e.g. `<Ctrl>Q`
+                // and `<Control>q`
+                let Some(key) = gtk::accelerator_parse(accel.as_str())
+                    .map(|(key, modifiers)| gtk::accelerator_name(key, modifiers).to_string())
+                else {
+                    problems.push(format!(
+                        "Can't read the key '{accel}' for '{}'",
+                        shortcut.action
+                    ));
+                    continue;
+                };
+                match taken.get(&key) {
+                    Some(&owner) if owner == shortcut.action => {}
+                    Some(&owner) => problems.push(format!(
+                        "'{accel}' is set for both '{owner}' and '{}', it stays with '{owner}'",
+                        shortcut.action
+                    )),
+                    None => {
+                        taken.insert(key, shortcut.action);
+                        kept.push(accel);
+                    }
+                }
+            }
+            bindings.push((shortcut, kept));
+        }
+
+        Self { bindings, problems }
+    }
+
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+
+    // Plain keys registered as application accelerators would fire before a
+    // focused entry sees them, so they are handled in the bubble phase here:
+    // whatever the focused widget doesn't use activates the action.
+    pub fn controller(&self) -> gtk::ShortcutController {
+        let controller = gtk::ShortcutController::new();
+        controller.set_propagation_phase(gtk::PropagationPhase::Bubble);
+        for (shortcut, accels) in &self.bindings {
+            let action = gtk::NamedAction::new(&format!("win.{}", shortcut.action));
+            for accel in accels {
+                let trigger = gtk::ShortcutTrigger::parse_string(accel);
+                controller.add_shortcut(gtk::Shortcut::new(trigger, Some(action.clone())));
+            }
+        }
+        controller
+    }
+
+    // Every bound action by group, with the keys as GTK shows them. Clicking
+    // the keys of an action and pressing a new combination hands it to
+    // `remap`, which returns why it was refused.
+    pub fn help_window(
+        &self,
+        parent: &impl IsA<gtk::Window>,
+        remap: impl Fn(&str, String) -> Result<(), String> + 'static,
+    ) -> gtk::Window {
+        let problems_label = gtk::Label::new(Some(&self.problems.join("\n")));
+        problems_label.set_css_classes(&["error-banner"]);
+        problems_label.set_halign(gtk::Align::Start);
+        problems_label.set_wrap(true);
+        problems_label.set_visible(!self.problems.is_empty());
+
+        let hint_label = gtk::Label::new(Some(
+            "Click the keys of an action, then press the new ones (Esc cancels)",
+        ));
+        hint_label.set_halign(gtk::Align::Start);
+
+        let groups_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
+        groups_box.set_css_classes(&["shortcuts"]);
+        groups_box.append(&problems_label);
+        groups_box.append(&hint_label);
+
+        // The action waiting for its new keys, with the button showing them
+        let capturing: Rc<RefCell<Option<(&'static str, gtk::Button)>>> =
+            Rc::new(RefCell::new(None));
+
+        for group_title in SHORTCUT_GROUPS {
+            let title_label = gtk::Label::new(Some(group_title));
+            title_label.set_css_classes(&["bold-title"]);
+            title_label.set_halign(gtk::Align::Start);
+
+            let grid = gtk::Grid::builder()
+                .column_spacing(20)
+                .row_spacing(4)
+                .build();
+            let mut row = 0;
+            for (shortcut, accels) in &self.bindings {
+                if shortcut.group != *group_title {
+                    continue;
+                }
+
+                let keys_button = gtk::Button::with_label(&keys_label(accels));
+                keys_button.set_halign(gtk::Align::Start);
+                let capturing_clone = capturing.clone();
+                let action = shortcut.action;
+                keys_button.connect_clicked(move |keys_button| {
+                    keys_button.set_label("Press the new keys…");
+                    if let Some((_, previous)) =
+                        capturing_clone.replace(Some((action, keys_button.clone())))
+                    {
+                        previous.set_label(&previous.tooltip_text().unwrap_or_default());
+                    }
+                });
+                keys_button.set_tooltip_text(Some(&keys_label(accels)));
+
+                let title_label = gtk::Label::new(Some(shortcut.title));
+                title_label.set_halign(gtk::Align::Start);
+                grid.attach(&keys_button, 0, row, 1, 1);
+                grid.attach(&title_label, 1, row, 1, 1);
+                row += 1;
+            }
+
+            groups_box.append(&title_label);
+            groups_box.append(&grid);
+        }
+
+        let groups_scroll = gtk::ScrolledWindow::builder()
+            .child(&groups_box)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(600)
+            .build();
+
+        let help_window = gtk::Window::builder()
+            .title("Keyboard Shortcuts")
+            .transient_for(parent)
+            .modal(true)
+            .child(&groups_scroll)
+            .build();
+
+        // Takes the next key press while an action waits for its keys
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+        key_controller.connect_key_pressed(move |_, key, _, modifiers| {
+            let Some((action, keys_button)) = capturing.borrow().clone() else {
+                return glib::Propagation::Proceed;
+            };
+            let modifiers = modifiers & gtk::accelerator_get_default_mod_mask();
+            if key == gtk::gdk::Key::Escape {
+                keys_button.set_label(&keys_button.tooltip_text().unwrap_or_default());
+            } else if gtk::accelerator_valid(key, modifiers) {
+                let accel = gtk::accelerator_name(key, modifiers).to_string();
+                match remap(action, accel.clone()) {
+                    Ok(()) => {
+                        let label = keys_label(&[accel]);
+                        keys_button.set_label(&label);
+                        keys_button.set_tooltip_text(Some(&label));
+                        problems_label.set_visible(false);
+                    }
+                    Err(reason) => {
+                        keys_button.set_label(&keys_button.tooltip_text().unwrap_or_default());
+                        problems_label.set_text(&reason);
+                        problems_label.set_visible(true);
+                    }
+                }
+            } else {
+                // A modifier on its own, wait for the rest of the combination
+                return glib::Propagation::Stop;
+            }
+            capturing.replace(None);
+            glib::Propagation::Stop
+        });
+        help_window.add_controller(key_controller);
+
+        help_window
+    }
+}
+
+fn keys_label(accels: &[String]) -> String {
+    let keys = accels
+        .iter()
+        .filter_map(|accel| gtk::accelerator_parse(accel.as_str()))
+        .map(|(key, modifiers)| gtk::accelerator_get_label(key, modifiers))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if keys.is_empty() {
+        "None".to_string()
+    } else {
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys_of<'a>(key_bindings: &'a KeyBindings, action: &str) -> &'a [String] {
+        key_bindings
+            .bindings
+            .iter()
+            .find(|(shortcut, _)| shortcut.action == action)
+            .map(|(_, accels)| accels.as_slice())
+            .unwrap()
+    }
+
+    fn overrides(keys: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        keys.iter()
+            .map(|(action, accels)| {
+                let accels = accels.iter().map(|accel| accel.to_string()).collect();
+                (action.to_string(), accels)
+            })
+            .collect()
+    }
+
+    #[gtk::test]
+    fn defaults_have_no_conflicts() {
+        let key_bindings = KeyBindings::new(&BTreeMap::new());
+        assert_eq!(key_bindings.problems(), &[] as &[String]);
+        assert_eq!(keys_of(&key_bindings, "play-pause"), ["space", "k"]);
+        assert_eq!(keys_of(&key_bindings, "quit"), ["<Control>q"]);
+    }
+
+    #[gtk::test]
+    fn conflicting_keys_stay_with_the_first_action() {
+        // Other spellings of the keys of play-pause and quit, stop comes
+        // before quit
+        let key_bindings =
+            KeyBindings::new(&overrides(&[("mute", &["K"]), ("stop", &["<Ctrl>Q", "x"])]));
+
+        assert_eq!(keys_of(&key_bindings, "play-pause"), ["space", "k"]);
+        assert!(keys_of(&key_bindings, "mute").is_empty());
+        assert_eq!(keys_of(&key_bindings, "stop"), ["<Ctrl>Q", "x"]);
+        assert!(keys_of(&key_bindings, "quit").is_empty());
+        assert_eq!(key_bindings.problems().len(), 2);
+        assert!(key_bindings.problems()[0].contains("stays with 'play-pause'"));
+        assert!(key_bindings.problems()[1].contains("stays with 'stop'"));
+    }
+
+    #[gtk::test]
+    fn unknown_actions_and_unreadable_keys_are_reported() {
+        let key_bindings = KeyBindings::new(&overrides(&[
+            ("dance", &["d"]),
+            ("stop", &["not a key", "<Shift>nonsense", "<Shift>q"]),
+        ]));
+
+        assert_eq!(keys_of(&key_bindings, "stop"), ["<Shift>q"]);
+        assert_eq!(key_bindings.problems().len(), 3);
+        assert!(key_bindings.problems()[0].contains("'dance'"));
+    }
+}
@@ -0,0 +1,145 @@
+use std::{path::PathBuf, str::FromStr};
+
+use chrono::Local;
+use gst_video::{prelude::*, VideoFrameRef};
+use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+
+use crate::{error::GstreamerError, LOG_DOMAIN};
+
+pub const SNAPSHOT_FORMAT_NAMES: &[&str] = &["png", "jpeg"];
+
+const CONVERT_TIMEOUT: gst::ClockTime = gst::ClockTime::from_seconds(2);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl SnapshotFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Png => "png",
+            SnapshotFormat::Jpeg => "jpg",
+        }
+    }
+
+    // Type names understood by gdk-pixbuf
+    fn pixbuf_type(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Png => "png",
+            SnapshotFormat::Jpeg => "jpeg",
+        }
+    }
+}
+
+impl FromStr for SnapshotFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "png" => Ok(SnapshotFormat::Png),
+            "jpeg" | "jpg" => Ok(SnapshotFormat::Jpeg),
+            _ => Err(format!(
+                "Unknown snapshot format '{name}', expected one of: {}",
+                SNAPSHOT_FORMAT_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    pub folder: PathBuf,
+    pub format: SnapshotFormat,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            folder: default_folder(),
+            format: SnapshotFormat::default(),
+        }
+    }
+}
+
+// Packed 8-bit RGB, three bytes per pixel without row padding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn from_sample(sample: &gst::Sample) -> Result<Self, GstreamerError> {
+        let caps = gst_video::VideoCapsBuilder::new()
+            .format(gst_video::VideoFormat::Rgb)
+            .build();
+        let rgb_sample = gst_video::convert_sample(sample, &caps, CONVERT_TIMEOUT)
+            .map_err(|err| GstreamerError::Snapshot(err.to_string()))?;
+
+        let (Some(buffer), Some(caps)) = (rgb_sample.buffer(), rgb_sample.caps()) else {
+            return Err(GstreamerError::Snapshot(
+                "converted sample is empty".to_string(),
+            ));
+        };
+        let info = gst_video::VideoInfo::from_caps(caps)?;
+        let frame = VideoFrameRef::from_buffer_ref_readable(buffer, &info)?;
+        let plane = frame.plane_data(0)?;
+        let stride = frame.plane_stride()[0] as usize;
+
+        let row_length = info.width() as usize * 3;
+        let mut data = Vec::with_capacity(row_length * info.height() as usize);
+        for row in plane.chunks(stride).take(info.height() as usize) {
+            data.extend_from_slice(&row[..row_length]);
+        }
+
+        Ok(Self {
+            width: info.width(),
+            height: info.height(),
+            data,
+        })
+    }
+
+    // Bytes per row of `data`
+    pub fn stride(&self) -> usize {
+        self.width as usize * 3
+    }
+
+    pub fn save(self, options: &SnapshotOptions) -> Result<PathBuf, GstreamerError> {
+        let (folder, format) = (&options.folder, options.format);
+        std::fs::create_dir_all(folder).map_err(|err| {
+            GstreamerError::Snapshot(format!("can't create '{}': {err}", folder.display()))
+        })?;
+        let path = folder.join(format!(
+            "snapshot-{}.{}",
+            Local::now().format("%Y%m%d-%H%M%S%.3f"),
+            format.extension()
+        ));
+
+        let (width, height, stride) = (self.width as i32, self.height as i32, self.stride());
+        let pixbuf = Pixbuf::from_mut_slice(
+            self.data,
+            Colorspace::Rgb,
+            false,
+            8,
+            width,
+            height,
+            stride as i32,
+        );
+        pixbuf
+            .savev(&path, format.pixbuf_type(), &[])
+            .map_err(|err| GstreamerError::Snapshot(err.to_string()))?;
+
+        glib::g_info!(LOG_DOMAIN, "Saved snapshot '{}'", path.display());
+        Ok(path)
+    }
+}
+
+fn default_folder() -> PathBuf {
+    dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
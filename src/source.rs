@@ -0,0 +1,181 @@
+use std::path::PathBuf;
+
+use gst::{prelude::*, Element};
+
+use crate::{error::GstreamerError, gstreamer::make_element, gstreamer::set_element_property};
+
+const FILE_SRC: &str = "filesrc";
+const HTTP_SRC: &str = "souphttpsrc";
+const RTSP_SRC: &str = "rtspsrc";
+const URI_SRC: &str = "urisourcebin";
+const RTSP_LATENCY_MS: u32 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    File,
+    Http,
+    Rtsp,
+    Other,
+}
+
+impl SourceKind {
+    pub fn from_uri(uri: &str) -> Self {
+        match glib::uri_parse_scheme(uri).as_deref() {
+            Some("file") => SourceKind::File,
+            Some("http") | Some("https") => SourceKind::Http,
+            Some("rtsp") | Some("rtsps") | Some("rtspt") | Some("rtspu") => SourceKind::Rtsp,
+            _ => SourceKind::Other,
+        }
+    }
+
+    pub fn factory_name(&self) -> &'static str {
+        match self {
+            SourceKind::File => FILE_SRC,
+            SourceKind::Http => HTTP_SRC,
+            SourceKind::Rtsp => RTSP_SRC,
+            SourceKind::Other => URI_SRC,
+        }
+    }
+
+    pub fn is_network(&self) -> bool {
+        matches!(self, SourceKind::Http | SourceKind::Rtsp)
+    }
+
+    // rtspsrc and urisourcebin expose one pad per stream once they know about them
+    pub fn has_dynamic_pads(&self) -> bool {
+        matches!(self, SourceKind::Rtsp | SourceKind::Other)
+    }
+}
+
+pub fn to_uri(path_or_uri: &str) -> Result<String, GstreamerError> {
+    if glib::uri_parse_scheme(path_or_uri).is_some() {
+        return Ok(path_or_uri.to_string());
+    }
+
+    std::path::absolute(path_or_uri)
+        .map_err(|err| err.to_string())
+        .and_then(|path| glib::filename_to_uri(path, None).map_err(|err| err.to_string()))
+        .map(|uri| uri.to_string())
+        .map_err(|reason| GstreamerError::InvalidUri {
+            uri: path_or_uri.to_string(),
+            reason,
+        })
+}
+
+pub fn local_path(uri: &str) -> Option<PathBuf> {
+    glib::filename_from_uri(uri)
+        .ok()
+        .map(|(path, _hostname)| path)
+}
+
+pub fn make_source(uri: &str) -> Result<Element, GstreamerError> {
+    let kind = SourceKind::from_uri(uri);
+    let source = make_element(kind.factory_name())?;
+
+    match kind {
+        SourceKind::File => {
+            let path = local_path(uri).ok_or_else(|| GstreamerError::InvalidUri {
+                uri: uri.to_string(),
+                reason: "not a local file".to_string(),
+            })?;
+            set_element_property(&source, "location", path.to_string_lossy().as_ref())?;
+        }
+        SourceKind::Http => {
+            set_element_property(&source, "location", uri)?;
+        }
+        SourceKind::Rtsp => {
+            set_element_property(&source, "location", uri)?;
+            set_element_property(&source, "latency", RTSP_LATENCY_MS)?;
+        }
+        SourceKind::Other => {
+            set_element_property(&source, "uri", uri)?;
+            set_element_property(&source, "use-buffering", true)?;
+        }
+    }
+
+    Ok(source)
+}
+
+pub fn is_live(pipeline: &gst::Pipeline) -> bool {
+    let mut query = gst::query::Latency::new();
+    pipeline.query(&mut query) && query.result().0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_media::{self, REQUEST_TIMEOUT};
+
+    #[test]
+    fn scheme_picks_the_source() {
+        for (uri, kind) in [
+            ("file:///tmp/clip.mkv", SourceKind::File),
+            ("http://example.com/clip.mp4", SourceKind::Http),
+            ("https://example.com/clip.mp4", SourceKind::Http),
+            ("rtsp://example.com/live", SourceKind::Rtsp),
+            ("rtspt://example.com/live", SourceKind::Rtsp),
+            ("smb://server/clip.mkv", SourceKind::Other),
+        ] {
+            assert_eq!(SourceKind::from_uri(uri), kind, "{uri}");
+        }
+        assert!(SourceKind::Rtsp.has_dynamic_pads() && SourceKind::Rtsp.is_network());
+        assert!(!SourceKind::File.has_dynamic_pads() && !SourceKind::File.is_network());
+    }
+
+    #[test]
+    fn paths_become_file_uris() {
+        assert_eq!(
+            to_uri("https://example.com/clip.mp4").unwrap(),
+            "https://example.com/clip.mp4"
+        );
+        let uri = to_uri("clip.mkv").unwrap();
+        assert!(uri.starts_with("file:///") && uri.ends_with("/clip.mkv"));
+        assert_eq!(
+            local_path(&uri).unwrap(),
+            std::path::absolute("clip.mkv").unwrap()
+        );
+        assert_eq!(local_path("http://example.com/clip.mp4"), None);
+    }
+
+    #[test]
+    fn http_source_reads_from_a_local_server() {
+        test_media::init();
+        let body: Vec<u8> = (0..64 * 1024).map(|index| (index % 251) as u8).collect();
+        let uri = test_media::serve_http(body.clone());
+        let output = test_media::temp_path("http-download.bin");
+
+        let source = make_source(&uri).unwrap();
+        assert_eq!(source.factory().unwrap().name(), HTTP_SRC);
+        let sink = make_element("filesink").unwrap();
+        set_element_property(&sink, "location", output.to_string_lossy().as_ref()).unwrap();
+        let pipeline = gst::Pipeline::new();
+        pipeline.add_many([&source, &sink]).unwrap();
+        source.link(&sink).unwrap();
+
+        test_media::run_to_eos(&pipeline).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), body);
+    }
+
+    #[test]
+    fn rtsp_source_connects_to_the_server() {
+        test_media::init();
+        let (uri, requests) = test_media::rtsp_stand_in();
+
+        let source = make_source(&uri).unwrap();
+        assert_eq!(source.factory().unwrap().name(), RTSP_SRC);
+        assert_eq!(source.property::<u32>("latency"), RTSP_LATENCY_MS);
+        let pipeline = gst::Pipeline::new();
+        pipeline.add(&source).unwrap();
+        // rtspsrc only connects once it goes to PAUSED, its pads come later
+        pipeline.set_state(gst::State::Paused).unwrap();
+
+        let request = requests.recv_timeout(REQUEST_TIMEOUT).unwrap();
+        pipeline.set_state(gst::State::Null).unwrap();
+        assert!(
+            request.starts_with(&format!("OPTIONS {uri} RTSP/1.0\r\n")),
+            "{request}"
+        );
+    }
+}
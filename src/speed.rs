@@ -0,0 +1,18 @@
+pub const PLAYBACK_SPEED_NAMES: &[&str] = &[
+    "-2×", "-1×", "0.25×", "0.5×", "0.75×", "1×", "1.25×", "1.5×", "2×",
+];
+pub const PLAYBACK_SPEEDS: &[f64] = &[-2.0, -1.0, 0.25, 0.5, 0.75, 1.0, 1.25, 1.5, 2.0];
+pub const NORMAL_SPEED_INDEX: u32 = 5;
+
+pub fn speed_from_index(index: u32) -> f64 {
+    PLAYBACK_SPEEDS.get(index as usize).copied().unwrap_or(1.0)
+}
+
+// Closest entry of the selector for a rate
+pub fn index_for_speed(rate: f64) -> u32 {
+    PLAYBACK_SPEEDS
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (*a - rate).abs().total_cmp(&(*b - rate).abs()))
+        .map_or(NORMAL_SPEED_INDEX, |(index, _)| index as u32)
+}
@@ -0,0 +1,241 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk::{gio, prelude::*, ApplicationWindow, Button, Orientation};
+
+use crate::{
+    error_banner::ErrorBanner,
+    gstreamer::{Backend, GstreamerManager},
+    playlist::Playlist,
+    settings::Settings,
+    source::local_path,
+    subtitles::{
+        SubtitlePosition, SubtitleStyle, MAX_FONT_SIZE, MIN_FONT_SIZE, SUBTITLE_EXTENSIONS,
+        SUBTITLE_POSITION_NAMES,
+    },
+    window::add_action,
+};
+
+// Button with a popover for the subtitle file and how subtitles are drawn
+pub struct SubtitleMenu {
+    subtitle_file_button: gtk::MenuButton,
+}
+
+impl SubtitleMenu {
+    pub fn new(
+        window: &ApplicationWindow,
+        settings: Rc<RefCell<Settings>>,
+        playlist: Rc<RefCell<Playlist>>,
+        gst_manager: &GstreamerManager,
+        error_banner: ErrorBanner,
+    ) -> Self {
+        let subtitle_file_button = gtk::MenuButton::builder()
+            .label("💬")
+            .tooltip_text("Subtitle file and style")
+            .valign(gtk::Align::Center)
+            .build();
+
+        let file_label = gtk::Label::new(None);
+        file_label.set_halign(gtk::Align::Start);
+        file_label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        file_label.set_max_width_chars(30);
+        let open_button = Button::with_label("Open…");
+        let remove_button = Button::with_label("Remove");
+
+        let file_box = gtk::Box::new(Orientation::Horizontal, 5);
+        file_box.append(&open_button);
+        file_box.append(&remove_button);
+
+        let font_size_spin =
+            gtk::SpinButton::with_range(MIN_FONT_SIZE.into(), MAX_FONT_SIZE.into(), 1.0);
+        let position_selector = gtk::DropDown::from_strings(SUBTITLE_POSITION_NAMES);
+        let offset_spin = gtk::SpinButton::with_range(-60.0, 60.0, 0.1);
+        offset_spin.set_digits(1);
+
+        let style_grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(10)
+            .build();
+        for (row, (title, widget)) in [
+            ("Font size", font_size_spin.upcast_ref::<gtk::Widget>()),
+            ("Position", position_selector.upcast_ref()),
+            ("Offset (s)", offset_spin.upcast_ref()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let title_label = gtk::Label::new(Some(title));
+            title_label.set_halign(gtk::Align::Start);
+            style_grid.attach(&title_label, 0, row as i32, 1, 1);
+            style_grid.attach(widget, 1, row as i32, 1, 1);
+        }
+
+        let popover_box = gtk::Box::new(Orientation::Vertical, 5);
+        popover_box.append(&file_label);
+        popover_box.append(&file_box);
+        popover_box.append(&style_grid);
+        let popover = gtk::Popover::builder().child(&popover_box).build();
+        subtitle_file_button.set_popover(Some(&popover));
+
+        // Only subtitle files drawn by the decodebin backend can be moved
+        let can_move = gst_manager.backend == Backend::Decodebin;
+        position_selector.set_sensitive(can_move);
+        let style = {
+            let settings = settings.borrow();
+            SubtitleStyle {
+                font_size: settings.subtitle_font_size,
+                position: if can_move {
+                    settings.subtitle_position
+                } else {
+                    SubtitlePosition::default()
+                },
+                offset_ms: 0,
+            }
+        };
+        if let Err(err) = gst_manager.set_subtitle_style(style) {
+            error_banner.show(&err);
+        }
+
+        // Set while the widgets show the manager's style, so that isn't applied again
+        let updating = Rc::new(Cell::new(false));
+        let show_style = {
+            let font_size_spin = font_size_spin.clone();
+            let position_selector = position_selector.clone();
+            let offset_spin = offset_spin.clone();
+            let updating = updating.clone();
+            move |style: SubtitleStyle| {
+                updating.set(true);
+                font_size_spin.set_value(style.font_size.into());
+                position_selector.set_selected(style.position.index());
+                offset_spin.set_value(style.offset_ms as f64 / 1000.0);
+                updating.set(false);
+            }
+        };
+        show_style(gst_manager.subtitle_style());
+
+        let apply_style = {
+            let font_size_spin = font_size_spin.clone();
+            let position_selector = position_selector.clone();
+            let offset_spin = offset_spin.clone();
+            let gst_manager = gst_manager.clone();
+            let error_banner = error_banner.clone();
+            let settings = settings.clone();
+            move || {
+                if updating.get() {
+                    return;
+                }
+                let style = SubtitleStyle {
+                    font_size: font_size_spin.value_as_int().unsigned_abs(),
+                    position: SubtitlePosition::from_index(position_selector.selected()),
+                    offset_ms: (offset_spin.value() * 1000.0).round() as i64,
+                };
+                match gst_manager.set_subtitle_style(style) {
+                    Ok(()) => {
+                        let mut settings = settings.borrow_mut();
+                        settings.subtitle_font_size = style.font_size;
+                        settings.subtitle_position = style.position;
+                    }
+                    Err(err) => {
+                        error_banner.show(&err);
+                        show_style(gst_manager.subtitle_style());
+                    }
+                }
+            }
+        };
+        let apply_style = Rc::new(apply_style);
+
+        let apply_style_clone = apply_style.clone();
+        font_size_spin.connect_value_changed(move |_| apply_style_clone());
+        let apply_style_clone = apply_style.clone();
+        position_selector.connect_selected_notify(move |_| apply_style_clone());
+        offset_spin.connect_value_changed(move |_| apply_style());
+
+        let refresh_file = {
+            let file_label = file_label.clone();
+            let remove_button = remove_button.clone();
+            let gst_manager = gst_manager.clone();
+            move || {
+                let file = gst_manager.subtitle_file();
+                let file_name = file
+                    .as_ref()
+                    .and_then(|path| path.file_name())
+                    .map(|name| name.to_string_lossy().to_string());
+                file_label.set_text(file_name.as_deref().unwrap_or("No subtitle file"));
+                file_label.set_tooltip_text(
+                    file.as_ref()
+                        .map(|path| path.to_string_lossy().to_string())
+                        .as_deref(),
+                );
+                remove_button.set_sensitive(file.is_some());
+            }
+        };
+        let refresh_file = Rc::new(refresh_file);
+
+        // Subtitle files next to the video are picked up on every new file
+        let refresh_file_clone = refresh_file.clone();
+        popover.connect_show(move |_| refresh_file_clone());
+
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = error_banner.clone();
+        let refresh_file_clone = refresh_file.clone();
+        remove_button.connect_clicked(move |_| {
+            if let Err(err) = gst_manager_clone.load_subtitles(None) {
+                error_banner_clone.show(&err);
+            }
+            refresh_file_clone();
+        });
+
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = error_banner.clone();
+        let playlist_clone = playlist.clone();
+        let window_clone = window.clone();
+        open_button.connect_clicked(move |_| {
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some("Subtitles"));
+            for extension in SUBTITLE_EXTENSIONS {
+                filter.add_suffix(extension);
+            }
+            let dialog = gtk::FileDialog::new();
+            dialog.set_default_filter(Some(&filter));
+            // Start next to the video, where subtitle files usually are
+            let folder = playlist_clone
+                .borrow()
+                .current()
+                .and_then(local_path)
+                .and_then(|path| path.parent().map(gio::File::for_path));
+            dialog.set_initial_folder(folder.as_ref());
+
+            let gst_manager = gst_manager_clone.clone();
+            let error_banner = error_banner_clone.clone();
+            let refresh_file = refresh_file.clone();
+            dialog.open(Some(&window_clone), gio::Cancellable::NONE, move |result| {
+                let Some(path) = result.ok().and_then(|file| file.path()) else {
+                    return;
+                };
+                match gst_manager.load_subtitles(Some(&path)) {
+                    Ok(()) => error_banner.hide(),
+                    Err(err) => error_banner.show(&err),
+                }
+                refresh_file();
+            });
+        });
+
+        let offset_spin_clone = offset_spin.clone();
+        add_action(window, "subtitles-earlier", move || {
+            offset_spin_clone.set_value(offset_spin_clone.value() - 0.1);
+        });
+        add_action(window, "subtitles-later", move || {
+            offset_spin.set_value(offset_spin.value() + 0.1);
+        });
+
+        Self {
+            subtitle_file_button,
+        }
+    }
+
+    pub fn widget(&self) -> &gtk::MenuButton {
+        &self.subtitle_file_button
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use gst::{prelude::*, Element};
+use gstreamer_app::{AppSrc, AppSrcCallbacks, AppStreamType};
+
+use crate::{
+    error::GstreamerError,
+    gstreamer::{make_element, set_element_property, set_element_property_from_str},
+    source::local_path,
+    LOG_DOMAIN,
+};
+
+const FILE_SRC: &str = "filesrc";
+const SUBTITLE_PARSE: &str = "subparse";
+const TEXT_OVERLAY: &str = "textoverlay";
+const ASS_RENDER: &str = "assrender";
+const FONT_FAMILY: &str = "Sans";
+
+// What subparse reads; ASS/SSA keep their styling when assrender is installed
+// and are flattened to plain text otherwise
+pub const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "vtt", "ass", "ssa", "sub"];
+pub const SUBTITLE_POSITION_NAMES: &[&str] = &["Bottom", "Middle", "Top"];
+pub const DEFAULT_FONT_SIZE: u32 = 18;
+pub const MIN_FONT_SIZE: u32 = 8;
+pub const MAX_FONT_SIZE: u32 = 96;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitlePosition {
+    #[default]
+    Bottom,
+    Middle,
+    Top,
+}
+
+impl SubtitlePosition {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => SubtitlePosition::Middle,
+            2 => SubtitlePosition::Top,
+            _ => SubtitlePosition::Bottom,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        match self {
+            SubtitlePosition::Bottom => 0,
+            SubtitlePosition::Middle => 1,
+            SubtitlePosition::Top => 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubtitlePosition::Bottom => "bottom",
+            SubtitlePosition::Middle => "middle",
+            SubtitlePosition::Top => "top",
+        }
+    }
+
+    // textoverlay's valignment nick
+    fn valignment(&self) -> &'static str {
+        match self {
+            SubtitlePosition::Bottom => "bottom",
+            SubtitlePosition::Middle => "center",
+            SubtitlePosition::Top => "top",
+        }
+    }
+}
+
+impl FromStr for SubtitlePosition {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bottom" => Ok(SubtitlePosition::Bottom),
+            "middle" => Ok(SubtitlePosition::Middle),
+            "top" => Ok(SubtitlePosition::Top),
+            _ => Err(format!(
+                "Unknown subtitle position '{name}', expected bottom, middle or top"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubtitleStyle {
+    // In points
+    pub font_size: u32,
+    pub position: SubtitlePosition,
+    // Milliseconds, positive shows the subtitles later
+    pub offset_ms: i64,
+}
+
+impl Default for SubtitleStyle {
+    fn default() -> Self {
+        Self {
+            font_size: DEFAULT_FONT_SIZE,
+            position: SubtitlePosition::default(),
+            offset_ms: 0,
+        }
+    }
+}
+
+impl SubtitleStyle {
+    pub fn font_desc(&self) -> String {
+        format!("{FONT_FAMILY} {}", self.font_size)
+    }
+
+    pub fn offset_ns(&self) -> i64 {
+        self.offset_ms.saturating_mul(1_000_000)
+    }
+}
+
+// A subtitle file next to the video with the same name, e.g. `clip.srt` or
+// `clip.en.srt` for `clip.mp4`. The exact name wins over language suffixes.
+pub fn find_sidecar(uri: &str) -> Option<PathBuf> {
+    let path = local_path(uri)?;
+    let stem = path.file_stem()?.to_str()?;
+    let folder = path.parent()?;
+
+    let mut candidates: Vec<PathBuf> = fs::read_dir(folder)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            let is_subtitle = candidate
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| SUBTITLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+            let candidate_stem = candidate.file_stem().and_then(|stem| stem.to_str());
+            is_subtitle
+                && candidate_stem.is_some_and(|candidate_stem| {
+                    candidate_stem == stem || candidate_stem.starts_with(&format!("{stem}."))
+                })
+        })
+        .collect();
+    candidates.sort_by_key(|candidate| {
+        let exact = candidate.file_stem().and_then(|stem| stem.to_str()) == Some(stem);
+        (!exact, candidate.clone())
+    });
+    candidates.into_iter().next()
+}
+
+// A subtitle file feeding the text pad of an overlay in the video branch of
+// the decodebin backend, after the embedded subtitles. ASS/SSA files go to
+// assrender when it is installed, everything else goes filesrc → subparse →
+// textoverlay.
+#[derive(Clone)]
+pub struct ExternalSubtitles {
+    overlay: Element,
+    ass_overlay: Option<Element>,
+    branch: Arc<Mutex<Option<SubtitleBranch>>>,
+}
+
+struct SubtitleBranch {
+    path: PathBuf,
+    // Upstream first, the last one feeds `overlay`
+    elements: Vec<Element>,
+    overlay: Element,
+}
+
+impl ExternalSubtitles {
+    pub fn new() -> Result<Self, GstreamerError> {
+        let overlay = make_element(TEXT_OVERLAY)?;
+        // Video never waits for a subtitle that isn't there
+        set_element_property(&overlay, "wait-text", false)?;
+        let ass_overlay = match make_element(ASS_RENDER) {
+            Ok(ass_overlay) => {
+                set_element_property(&ass_overlay, "wait-text", false)?;
+                Some(ass_overlay)
+            }
+            Err(err) => {
+                glib::g_info!(LOG_DOMAIN, "{err}, ASS/SSA files are shown as plain text");
+                None
+            }
+        };
+        Ok(Self {
+            overlay,
+            ass_overlay,
+            branch: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    // In the order they are linked into the video branch, each through its
+    // `video_sink` pad
+    pub fn overlays(&self) -> impl Iterator<Item = &Element> {
+        std::iter::once(&self.overlay).chain(self.ass_overlay.as_ref())
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        self.branch
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|branch| branch.path.clone())
+    }
+
+    // The caller seeks afterwards when the pipeline is already running, so
+    // the new branch starts at the current position
+    pub fn load(
+        &self,
+        bin: &gst::Bin,
+        path: &Path,
+        style: &SubtitleStyle,
+    ) -> Result<(), GstreamerError> {
+        self.unload(bin)?;
+
+        let ass_branch = self.ass_overlay.as_ref().zip(ass_caps_name(path)).and_then(
+            |(ass_overlay, caps_name)| {
+                let script = fs::read_to_string(path)
+                    .ok()
+                    .and_then(|text| AssScript::parse(&text));
+                if script.is_none() {
+                    glib::g_warning!(
+                        LOG_DOMAIN,
+                        "Can't read '{}' as ASS/SSA, showing it as plain text",
+                        path.display()
+                    );
+                }
+                script.map(|script| (ass_overlay, script.source(caps_name)))
+            },
+        );
+        let (elements, overlay, description) = match ass_branch {
+            Some((ass_overlay, source)) => (vec![source], ass_overlay, "subtitle file → assrender"),
+            None => {
+                let source = make_element(FILE_SRC)?;
+                set_element_property(&source, "location", path.to_string_lossy().as_ref())?;
+                (
+                    vec![source, make_element(SUBTITLE_PARSE)?],
+                    &self.overlay,
+                    "subtitle file → subparse → textoverlay",
+                )
+            }
+        };
+        bin.add_many(&elements)?;
+
+        let linked = Element::link_many(&elements).and_then(|_| {
+            Element::link_pads(
+                &elements[elements.len() - 1],
+                Some("src"),
+                overlay,
+                Some("text_sink"),
+            )
+        });
+        if linked.is_err() {
+            for element in &elements {
+                bin.remove(element).ok();
+            }
+            return Err(GstreamerError::Link(description.to_string()));
+        }
+
+        *self.branch.lock().unwrap() = Some(SubtitleBranch {
+            path: path.to_path_buf(),
+            elements: elements.clone(),
+            overlay: overlay.clone(),
+        });
+        // The offset has to be on the pad before the first subtitle goes out
+        self.set_style(style)?;
+        for element in &elements {
+            element.sync_state_with_parent()?;
+        }
+        glib::g_info!(
+            LOG_DOMAIN,
+            "Loaded subtitles from '{}' into {}",
+            path.display(),
+            overlay.name()
+        );
+        Ok(())
+    }
+
+    pub fn unload(&self, bin: &gst::Bin) -> Result<(), GstreamerError> {
+        let Some(branch) = self.branch.lock().unwrap().take() else {
+            return Ok(());
+        };
+        // Stop the branch before unlinking, so it never pushes into nothing
+        for element in &branch.elements {
+            element
+                .set_state(gst::State::Null)
+                .map_err(|_| GstreamerError::StateChange {
+                    element: element.name().to_string(),
+                    state: gst::State::Null,
+                })?;
+        }
+        Element::unlink_many(&branch.elements);
+        if let Some(last) = branch.elements.last() {
+            last.unlink(&branch.overlay);
+        }
+        bin.remove_many(&branch.elements)?;
+        Ok(())
+    }
+
+    // Font size and position only apply to textoverlay, ASS/SSA scripts bring
+    // their own
+    pub fn set_style(&self, style: &SubtitleStyle) -> Result<(), GstreamerError> {
+        set_element_property(&self.overlay, "font-desc", style.font_desc())?;
+        set_element_property_from_str(&self.overlay, "valignment", style.position.valignment())?;
+        if let Some(src_pad) = self
+            .branch
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|branch| branch.elements.last())
+            .and_then(|last| last.static_pad("src"))
+        {
+            src_pad.set_offset(style.offset_ns());
+        }
+        Ok(())
+    }
+}
+
+// assrender's text caps for the files it draws
+fn ass_caps_name(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "ass" => Some("application/x-ass"),
+        "ssa" => Some("application/x-ssa"),
+        _ => None,
+    }
+}
+
+// An ASS/SSA script split the way matroskademux hands it to assrender: the
+// header goes into the caps and every Dialogue line becomes a timed buffer
+#[derive(Debug, PartialEq)]
+struct AssScript {
+    header: String,
+    events: Vec<AssEvent>,
+}
+
+#[derive(Debug, PartialEq)]
+struct AssEvent {
+    start: gst::ClockTime,
+    duration: gst::ClockTime,
+    // `ReadOrder,Layer,` and the fields after the end time
+    chunk: String,
+}
+
+impl AssScript {
+    // None without an [Events] section
+    fn parse(text: &str) -> Option<Self> {
+        let mut header = String::new();
+        let mut events = Vec::new();
+        let mut has_events = false;
+        let mut in_events = false;
+        for line in text.trim_start_matches('\u{feff}').lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_events = trimmed.eq_ignore_ascii_case("[events]");
+                has_events |= in_events;
+            }
+            if in_events {
+                if let Some(fields) = trimmed.strip_prefix("Dialogue:") {
+                    match AssEvent::parse(events.len(), fields) {
+                        Some(event) => events.push(event),
+                        None => glib::g_debug!(LOG_DOMAIN, "Skipping subtitle line '{trimmed}'"),
+                    }
+                    continue;
+                }
+                if trimmed.starts_with("Comment:") {
+                    continue;
+                }
+            }
+            header.push_str(line);
+            header.push('\n');
+        }
+        // Buffers have to go out in time order, the file needn't be
+        events.sort_by_key(|event| event.start);
+        has_events.then_some(Self { header, events })
+    }
+
+    // An appsrc pushing the events, which starts over from the right event
+    // after a seek
+    fn source(self, caps_name: &str) -> Element {
+        let caps = gst::Caps::builder(caps_name)
+            .field(
+                "codec_data",
+                gst::Buffer::from_slice(self.header.into_bytes()),
+            )
+            .build();
+        let events = Arc::new(self.events);
+        let next = Arc::new(Mutex::new(0));
+
+        let events_clone = events.clone();
+        let next_clone = next.clone();
+        let callbacks = AppSrcCallbacks::builder()
+            .need_data(move |source, _| {
+                let mut next = next_clone.lock().unwrap();
+                let Some(event) = events_clone.get(*next) else {
+                    let _ = source.end_of_stream();
+                    return;
+                };
+                *next += 1;
+                let mut buffer = gst::Buffer::from_slice(event.chunk.clone().into_bytes());
+                if let Some(buffer) = buffer.get_mut() {
+                    buffer.set_pts(event.start);
+                    buffer.set_duration(event.duration);
+                }
+                let _ = source.push_buffer(buffer);
+            })
+            .seek_data(move |_, position| {
+                let position = gst::ClockTime::from_nseconds(position);
+                *next.lock().unwrap() = events
+                    .iter()
+                    .position(|event| event.start + event.duration > position)
+                    .unwrap_or(events.len());
+                true
+            })
+            .build();
+
+        AppSrc::builder()
+            .caps(&caps)
+            .format(gst::Format::Time)
+            .stream_type(AppStreamType::Seekable)
+            .callbacks(callbacks)
+            .build()
+            .upcast()
+    }
+}
+
+impl AssEvent {
+    // `fields` is what follows `Dialogue:`, Layer (Marked in SSA), Start,
+    // End and the rest as the Format line orders them
+    fn parse(read_order: usize, fields: &str) -> Option<Self> {
+        let mut fields = fields.splitn(4, ',');
+        let layer = fields.next()?.trim();
+        let start = parse_ass_time(fields.next()?)?;
+        let end = parse_ass_time(fields.next()?)?;
+        let rest = fields.next()?;
+        Some(Self {
+            start,
+            duration: end.saturating_sub(start),
+            chunk: format!("{read_order},{layer},{rest}"),
+        })
+    }
+}
+
+// `H:MM:SS.cc`, some writers put more digits after the dot
+fn parse_ass_time(time: &str) -> Option<gst::ClockTime> {
+    let mut parts = time.trim().splitn(3, ':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds = parts.next()?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let seconds: u64 = seconds.parse().ok()?;
+    if !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let digits = &fraction[..fraction.len().min(9)];
+    let fraction_ns = match digits.parse::<u64>() {
+        Ok(fraction) => fraction * 10u64.pow(9 - digits.len() as u32),
+        Err(_) => 0,
+    };
+    Some(
+        gst::ClockTime::from_seconds((hours * 60 + minutes) * 60 + seconds)
+            + gst::ClockTime::from_nseconds(fraction_ns),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "\u{feff}[Script Info]
+ScriptType: v4.00+
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour
+Style: Default,Arial,20,&H00FFFFFF
+
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+Dialogue: 0,0:00:05.00,0:00:07.50,Default,,0,0,0,,Second, with a comma
+Comment: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Not shown
+Dialogue: 1,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\an8}First
+Dialogue: broken line
+";
+
+    #[test]
+    fn scripts_split_into_header_and_sorted_events() {
+        let script = AssScript::parse(SCRIPT).unwrap();
+        assert!(script.header.starts_with("[Script Info]\n"));
+        assert!(script.header.ends_with(
+            "[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, \
+             Effect, Text\n"
+        ));
+        assert_eq!(
+            script.events,
+            vec![
+                AssEvent {
+                    start: gst::ClockTime::from_seconds(1),
+                    duration: gst::ClockTime::from_seconds(1),
+                    chunk: "1,1,Default,,0,0,0,,{\\an8}First".to_string(),
+                },
+                AssEvent {
+                    start: gst::ClockTime::from_seconds(5),
+                    duration: gst::ClockTime::from_mseconds(2500),
+                    chunk: "0,0,Default,,0,0,0,,Second, with a comma".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn scripts_without_events_are_rejected() {
+        assert_eq!(
+            AssScript::parse("1\n00:00:01,000 --> 00:00:02,000\nHi\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn times_parse_with_any_precision() {
+        assert_eq!(
+            parse_ass_time("1:02:03.45"),
+            Some(gst::ClockTime::from_mseconds(3_723_450))
+        );
+        assert_eq!(
+            parse_ass_time(" 0:00:01.123456 "),
+            Some(gst::ClockTime::from_useconds(1_123_456))
+        );
+        assert_eq!(
+            parse_ass_time("0:00:07"),
+            Some(gst::ClockTime::from_seconds(7))
+        );
+        assert_eq!(parse_ass_time("0:00:0x.00"), None);
+        assert_eq!(parse_ass_time("0:00"), None);
+    }
+
+    #[test]
+    fn only_ass_and_ssa_go_to_assrender() {
+        assert_eq!(
+            ass_caps_name(Path::new("clip.en.ASS")),
+            Some("application/x-ass")
+        );
+        assert_eq!(
+            ass_caps_name(Path::new("clip.ssa")),
+            Some("application/x-ssa")
+        );
+        assert_eq!(ass_caps_name(Path::new("clip.srt")), None);
+    }
+}
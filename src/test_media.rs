@@ -0,0 +1,148 @@
+// Generated media and stand-in servers for the tests that run real pipelines
+use std::{
+    fs,
+    io::{Read, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use gst::{prelude::*, MessageView};
+
+const TIMEOUT: gst::ClockTime = gst::ClockTime::from_seconds(10);
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Every generated clip is this solid colour, as R, G, B
+pub const CLIP_COLOR: [u8; 3] = [0x20, 0x60, 0xc0];
+pub const CLIP_WIDTH: u32 = 64;
+pub const CLIP_HEIGHT: u32 = 48;
+pub const CLIP_FRAMES: u32 = 30;
+pub const CLIP_TITLE: &str = "Test clip";
+
+pub fn init() {
+    gst::init().expect("Failed to init GStreamer");
+}
+
+// Inside a folder of this test process, so parallel runs don't collide
+pub fn temp_path(name: &str) -> PathBuf {
+    let folder = std::env::temp_dir().join(format!("gst-video-player-{}", std::process::id()));
+    fs::create_dir_all(&folder).unwrap();
+    folder.join(name)
+}
+
+pub fn uri(path: &Path) -> String {
+    glib::filename_to_uri(path, None).unwrap().to_string()
+}
+
+// One second of uncompressed video plus audio in Matroska, so frames decode
+// to exactly `CLIP_COLOR`
+pub fn write_clip(name: &str) -> PathBuf {
+    let path = temp_path(name);
+    let [red, green, blue] = CLIP_COLOR;
+    let color = u32::from_be_bytes([0xff, red, green, blue]);
+    let description = format!(
+        "videotestsrc pattern=solid-color foreground-color={color} num-buffers={CLIP_FRAMES} \
+         ! video/x-raw,format=RGB,width={CLIP_WIDTH},height={CLIP_HEIGHT},framerate=30/1 \
+         ! taginject tags=\"title=\\\"{CLIP_TITLE}\\\"\" ! matroskamux name=mux \
+         ! filesink location=\"{}\" \
+         audiotestsrc num-buffers=43 ! audio/x-raw,format=S16LE,rate=44100,channels=2 ! mux.",
+        path.display()
+    );
+    let pipeline = gst::parse::launch(&description)
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+    run_to_eos(&pipeline).unwrap();
+    path
+}
+
+// The audio of `write_clip` alone, for the branches that have to cope without video
+pub fn write_audio_clip(name: &str) -> PathBuf {
+    let path = temp_path(name);
+    let description = format!(
+        "audiotestsrc num-buffers=43 ! audio/x-raw,format=S16LE,rate=44100,channels=2 \
+         ! matroskamux ! filesink location=\"{}\"",
+        path.display()
+    );
+    let pipeline = gst::parse::launch(&description)
+        .unwrap()
+        .downcast::<gst::Pipeline>()
+        .unwrap();
+    run_to_eos(&pipeline).unwrap();
+    path
+}
+
+// Plays `pipeline` until it ends and leaves it in NULL
+pub fn run_to_eos(pipeline: &gst::Pipeline) -> Result<(), String> {
+    pipeline.set_state(gst::State::Playing).unwrap();
+    let result = wait_for(pipeline, gst::MessageType::Eos);
+    pipeline.set_state(gst::State::Null).unwrap();
+    result
+}
+
+// Waits for the first `message_type` message, or fails on an error message
+pub fn wait_for(pipeline: &gst::Pipeline, message_type: gst::MessageType) -> Result<(), String> {
+    let bus = pipeline.bus().unwrap();
+    match bus.timed_pop_filtered(TIMEOUT, &[message_type, gst::MessageType::Error]) {
+        Some(message) => match message.view() {
+            MessageView::Error(err) => Err(format!("{} ({:?})", err.error(), err.debug())),
+            _ => Ok(()),
+        },
+        None => Err(format!("No {message_type:?} within {TIMEOUT}")),
+    }
+}
+
+// Waits until `pipeline` prerolled in PAUSED
+pub fn preroll(pipeline: &gst::Pipeline) -> Result<(), String> {
+    pipeline.set_state(gst::State::Paused).unwrap();
+    match pipeline.state(TIMEOUT) {
+        (Ok(_), gst::State::Paused, _) => Ok(()),
+        (result, state, _) => Err(format!("Stuck in {state:?}: {result:?}")),
+    }
+}
+
+// Answers every GET with `body`, for as long as the test process runs
+pub fn serve_http(body: Vec<u8>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            read_request(&mut stream);
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).ok();
+            stream.write_all(&body).ok();
+        }
+    });
+    format!("http://127.0.0.1:{port}/clip.mkv")
+}
+
+// Accepts a single RTSP connection and hands over the first request it reads
+pub fn rtsp_stand_in() -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            sender.send(read_request(&mut stream)).ok();
+        }
+    });
+    (format!("rtsp://127.0.0.1:{port}/test"), receiver)
+}
+
+fn read_request(stream: &mut impl Read) -> String {
+    let mut request = Vec::new();
+    let mut chunk = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => request.extend_from_slice(&chunk[..read]),
+        }
+    }
+    String::from_utf8_lossy(&request).into_owned()
+}
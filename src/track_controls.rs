@@ -0,0 +1,151 @@
+use std::{cell::Cell, rc::Rc};
+
+use gtk::{prelude::*, ApplicationWindow, Orientation};
+
+use crate::{
+    bus::PlayerEvent, error_banner::ErrorBanner, gstreamer::GstreamerManager, tracks::TrackKind,
+    window::add_action,
+};
+
+// Audio track and subtitle track selectors, refreshed as the streams appear
+pub struct TrackControls {
+    container: gtk::Box,
+}
+
+impl TrackControls {
+    pub fn new(
+        window: &ApplicationWindow,
+        gst_manager: &GstreamerManager,
+        error_banner: ErrorBanner,
+    ) -> Self {
+        let audio_track_selector = gtk::DropDown::from_strings(&[]);
+        audio_track_selector.set_valign(gtk::Align::Center);
+        audio_track_selector.set_tooltip_text(Some("Audio track (a)"));
+        let subtitle_track_selector = gtk::DropDown::from_strings(&[]);
+        subtitle_track_selector.set_valign(gtk::Align::Center);
+        subtitle_track_selector.set_tooltip_text(Some("Subtitles (c)"));
+
+        let container = gtk::Box::new(Orientation::Horizontal, 5);
+        container.append(&audio_track_selector);
+        container.append(&subtitle_track_selector);
+
+        // Set while the lists are rebuilt, so that doesn't switch tracks
+        let refreshing = Rc::new(Cell::new(false));
+
+        let refresh = {
+            let audio_track_selector = audio_track_selector.clone();
+            let subtitle_track_selector = subtitle_track_selector.clone();
+            let gst_manager = gst_manager.clone();
+            let refreshing = refreshing.clone();
+            move || {
+                refreshing.set(true);
+                let audio_tracks = gst_manager.tracks(TrackKind::Audio);
+                set_track_list(
+                    &audio_track_selector,
+                    audio_tracks.iter().map(String::as_str),
+                    gst_manager.selected_track(TrackKind::Audio),
+                );
+                audio_track_selector.set_sensitive(audio_tracks.len() > 1);
+
+                // "Off" comes first, so track n sits at n + 1
+                let subtitle_tracks = gst_manager.tracks(TrackKind::Subtitle);
+                set_track_list(
+                    &subtitle_track_selector,
+                    std::iter::once("Off").chain(subtitle_tracks.iter().map(String::as_str)),
+                    Some(
+                        gst_manager
+                            .selected_track(TrackKind::Subtitle)
+                            .map_or(0, |index| index + 1),
+                    ),
+                );
+                subtitle_track_selector.set_sensitive(!subtitle_tracks.is_empty());
+                refreshing.set(false);
+            }
+        };
+        refresh();
+
+        let refresh_clone = refresh.clone();
+        gst_manager.bus.subscribe(move |event| {
+            // Tags carry the languages, they often arrive after the track
+            if let PlayerEvent::StreamCollection(_)
+            | PlayerEvent::StreamsSelected(_)
+            | PlayerEvent::TracksChanged
+            | PlayerEvent::Tags(_) = event
+            {
+                refresh_clone();
+            }
+        });
+
+        for (selector, kind) in [
+            (&audio_track_selector, TrackKind::Audio),
+            (&subtitle_track_selector, TrackKind::Subtitle),
+        ] {
+            let gst_manager_clone = gst_manager.clone();
+            let error_banner_clone = error_banner.clone();
+            let refreshing_clone = refreshing.clone();
+            let refresh_clone = refresh.clone();
+            selector.connect_selected_notify(move |selector| {
+                if refreshing_clone.get() || selector.selected() == gtk::INVALID_LIST_POSITION {
+                    return;
+                }
+                let selected = selector.selected() as usize;
+                let index = match kind {
+                    TrackKind::Audio => Some(selected),
+                    TrackKind::Subtitle => selected.checked_sub(1),
+                };
+                if gst_manager_clone.selected_track(kind) == index {
+                    return;
+                }
+                if let Err(err) = gst_manager_clone.select_track(kind, index) {
+                    error_banner_clone.show(&err);
+                    refresh_clone();
+                }
+            });
+        }
+
+        add_action(window, "next-audio-track", move || {
+            step_track_selector(&audio_track_selector);
+        });
+        add_action(window, "next-subtitle-track", move || {
+            step_track_selector(&subtitle_track_selector);
+        });
+
+        Self { container }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+}
+
+fn set_track_list<'a>(
+    selector: &gtk::DropDown,
+    names: impl Iterator<Item = &'a str>,
+    selected: Option<usize>,
+) {
+    let names: Vec<&str> = names.collect();
+    let Some(track_list) = selector.model().and_downcast::<gtk::StringList>() else {
+        return;
+    };
+    // Tag updates come often, an unchanged list keeps an open popup open
+    let unchanged = track_list.n_items() as usize == names.len()
+        && (0..track_list.n_items())
+            .zip(&names)
+            .all(|(position, name)| track_list.string(position).as_deref() == Some(*name));
+    if !unchanged {
+        track_list.splice(0, track_list.n_items(), &names);
+    }
+    selector.set_selected(
+        selected
+            .and_then(|selected| u32::try_from(selected).ok())
+            .unwrap_or(gtk::INVALID_LIST_POSITION),
+    );
+}
+
+// Wraps around to the first entry
+fn step_track_selector(selector: &gtk::DropDown) {
+    let track_count = selector.model().map_or(0, |model| model.n_items());
+    if track_count > 1 {
+        selector.set_selected((selector.selected().wrapping_add(1)) % track_count);
+    }
+}
@@ -0,0 +1,314 @@
+use std::sync::{Arc, Mutex};
+
+use gst::{prelude::*, Element, Pipeline};
+
+use crate::{
+    error::GstreamerError,
+    gstreamer::{make_element, set_element_property},
+};
+
+pub const TRACKS_CHANGED: &str = "tracks-changed";
+
+const SELECTOR: &str = "input-selector";
+const SUBTITLE_OVERLAY: &str = "subtitleoverlay";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    Audio,
+    Subtitle,
+}
+
+impl TrackKind {
+    fn stream_type(self) -> gst::StreamType {
+        match self {
+            TrackKind::Audio => gst::StreamType::AUDIO,
+            TrackKind::Subtitle => gst::StreamType::TEXT,
+        }
+    }
+
+    // Caps name prefixes of the decoded pads
+    fn from_caps_name(name: &str) -> Option<Self> {
+        if name.starts_with("audio/") {
+            Some(TrackKind::Audio)
+        } else if name.starts_with("text/") || name.starts_with("subpicture/") {
+            Some(TrackKind::Subtitle)
+        } else {
+            None
+        }
+    }
+}
+
+// Audio and subtitle streams of the decodebin backend. Every decoded stream
+// gets a pad on an input-selector and switching tracks moves the active pad;
+// subtitles are drawn by a subtitleoverlay right after the filters.
+#[derive(Clone)]
+pub struct TrackSelectors {
+    pipeline: Pipeline,
+    pub audio_selector: Element,
+    subtitle_selector: Element,
+    pub subtitle_overlay: Element,
+    // Selector pads in the order the streams showed up
+    pads: Arc<Mutex<Vec<(TrackKind, gst::Pad)>>>,
+}
+
+impl TrackSelectors {
+    pub fn new(pipeline: &Pipeline) -> Result<Self, GstreamerError> {
+        let subtitle_selector = make_element(SELECTOR)?;
+        // Subtitles are sparse, waiting on them would stall the other tracks
+        set_element_property(&subtitle_selector, "sync-streams", false)?;
+
+        Ok(Self {
+            pipeline: pipeline.clone(),
+            audio_selector: make_element(SELECTOR)?,
+            subtitle_selector,
+            subtitle_overlay: make_element(SUBTITLE_OVERLAY)?,
+            pads: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    pub fn elements(&self) -> [&Element; 3] {
+        [
+            &self.audio_selector,
+            &self.subtitle_selector,
+            &self.subtitle_overlay,
+        ]
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.pads
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(kind, _)| *kind == TrackKind::Audio)
+    }
+
+    // Called from decodebin's pad-added, on a streaming thread. Pads that are
+    // neither audio nor subtitles are left alone.
+    pub fn link_decoded_pad(
+        &self,
+        caps_name: &str,
+        src_pad: &gst::Pad,
+    ) -> Result<(), GstreamerError> {
+        let Some(kind) = TrackKind::from_caps_name(caps_name) else {
+            return Ok(());
+        };
+        let selector = self.selector(kind);
+
+        let mut pads = self.pads.lock().unwrap();
+        // The overlay only waits for subtitles once a subtitle track is linked
+        if kind == TrackKind::Subtitle
+            && !pads
+                .iter()
+                .any(|(pad_kind, _)| *pad_kind == TrackKind::Subtitle)
+        {
+            Element::link_pads(
+                selector,
+                Some("src"),
+                &self.subtitle_overlay,
+                Some("subtitle_sink"),
+            )
+            .map_err(|_| GstreamerError::Link("subtitle selector → overlay".to_string()))?;
+        }
+
+        let sink_pad = selector
+            .request_pad_simple("sink_%u")
+            .ok_or_else(|| glib::bool_error!("{} has no free sink pad", selector.name()))?;
+        src_pad.link(&sink_pad).map_err(|err| {
+            GstreamerError::Link(format!("{} → selector: {err:?}", src_pad.name()))
+        })?;
+        pads.push((kind, sink_pad));
+        drop(pads);
+
+        self.post_tracks_changed();
+        Ok(())
+    }
+
+    // Lets go of the pads of the previous file's decodebins
+    pub fn reset(&self) {
+        let pads = std::mem::take(&mut *self.pads.lock().unwrap());
+        for (kind, pad) in pads {
+            self.selector(kind).release_request_pad(&pad);
+        }
+        if let (Some(src_pad), Some(sink_pad)) = (
+            self.subtitle_selector.static_pad("src"),
+            self.subtitle_overlay.static_pad("subtitle_sink"),
+        ) {
+            src_pad.unlink(&sink_pad).ok();
+        }
+        self.post_tracks_changed();
+    }
+
+    pub fn tracks(&self, kind: TrackKind) -> Vec<String> {
+        self.pads_of(kind)
+            .iter()
+            .enumerate()
+            .map(|(index, pad)| {
+                let tags = pad.property::<Option<gst::TagList>>("tags");
+                track_label(index, tags.as_ref())
+            })
+            .collect()
+    }
+
+    pub fn selected(&self, kind: TrackKind) -> Option<usize> {
+        if kind == TrackKind::Subtitle && self.subtitle_overlay.property::<bool>("silent") {
+            return None;
+        }
+        let pads = self.pads_of(kind);
+        let active_pad = self
+            .selector(kind)
+            .property::<Option<gst::Pad>>("active-pad");
+        // Until data flows the selector hasn't picked a pad, it takes the first one
+        match active_pad {
+            Some(active_pad) => pads.iter().position(|pad| *pad == active_pad),
+            None => (!pads.is_empty()).then_some(0),
+        }
+    }
+
+    // `None` hides the subtitles, audio always keeps a track
+    pub fn select(&self, kind: TrackKind, index: Option<usize>) -> Result<(), GstreamerError> {
+        if kind == TrackKind::Subtitle {
+            set_element_property(&self.subtitle_overlay, "silent", index.is_none())?;
+        }
+        let Some(index) = index else {
+            return Ok(());
+        };
+        let pad = self.pads_of(kind).get(index).cloned().ok_or_else(|| {
+            GstreamerError::Unsupported(format!("There is no track {}", index + 1))
+        })?;
+        set_element_property(self.selector(kind), "active-pad", &pad)
+    }
+
+    fn selector(&self, kind: TrackKind) -> &Element {
+        match kind {
+            TrackKind::Audio => &self.audio_selector,
+            TrackKind::Subtitle => &self.subtitle_selector,
+        }
+    }
+
+    fn pads_of(&self, kind: TrackKind) -> Vec<gst::Pad> {
+        self.pads
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(pad_kind, _)| *pad_kind == kind)
+            .map(|(_, pad)| pad.clone())
+            .collect()
+    }
+
+    fn post_tracks_changed(&self) {
+        self.pipeline
+            .post_message(gst::message::Application::new(gst::Structure::new_empty(
+                TRACKS_CHANGED,
+            )))
+            .ok();
+    }
+}
+
+// Audio and subtitle streams of the playbin3 backend, from the stream
+// collection and streams-selected messages it posts
+#[derive(Clone, Default)]
+pub struct StreamSelection {
+    collection: Arc<Mutex<Option<gst::StreamCollection>>>,
+    selected: Arc<Mutex<Vec<String>>>,
+}
+
+impl StreamSelection {
+    pub fn set_collection(&self, collection: &gst::StreamCollection) {
+        *self.collection.lock().unwrap() = Some(collection.clone());
+    }
+
+    pub fn set_selected(&self, streams: &[gst::Stream]) {
+        *self.selected.lock().unwrap() = streams
+            .iter()
+            .filter_map(|stream| stream.stream_id().map(|id| id.to_string()))
+            .collect();
+    }
+
+    pub fn tracks(&self, kind: TrackKind) -> Vec<String> {
+        self.streams_of(kind)
+            .iter()
+            .enumerate()
+            .map(|(index, stream)| track_label(index, stream.tags().as_ref()))
+            .collect()
+    }
+
+    pub fn selected(&self, kind: TrackKind) -> Option<usize> {
+        let selected = self.selected.lock().unwrap();
+        self.streams_of(kind).iter().position(|stream| {
+            stream
+                .stream_id()
+                .is_some_and(|id| selected.iter().any(|selected| *selected == id))
+        })
+    }
+
+    // Keeps the streams of the other kinds selected, `None` drops the kind
+    pub fn select(
+        &self,
+        playbin: &Element,
+        kind: TrackKind,
+        index: Option<usize>,
+    ) -> Result<(), GstreamerError> {
+        let collection = self.collection.lock().unwrap().clone();
+        let Some(collection) = collection else {
+            return Ok(());
+        };
+
+        let selected = self.selected.lock().unwrap().clone();
+        let mut stream_ids: Vec<String> = collection
+            .iter()
+            .filter(|stream| !stream.stream_type().intersects(kind.stream_type()))
+            .filter_map(|stream| stream.stream_id())
+            .map(|id| id.to_string())
+            .filter(|id| selected.contains(id))
+            .collect();
+        if let Some(index) = index {
+            let stream = self
+                .streams_of(kind)
+                .into_iter()
+                .nth(index)
+                .ok_or_else(|| {
+                    GstreamerError::Unsupported(format!("There is no track {}", index + 1))
+                })?;
+            stream_ids.extend(stream.stream_id().map(|id| id.to_string()));
+        }
+
+        let stream_ids: Vec<&str> = stream_ids.iter().map(String::as_str).collect();
+        if playbin.send_event(gst::event::SelectStreams::new(&stream_ids)) {
+            Ok(())
+        } else {
+            Err(GstreamerError::Unsupported(
+                "The stream doesn't allow switching tracks".to_string(),
+            ))
+        }
+    }
+
+    fn streams_of(&self, kind: TrackKind) -> Vec<gst::Stream> {
+        self.collection
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|collection| {
+                collection
+                    .iter()
+                    .filter(|stream| stream.stream_type().intersects(kind.stream_type()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+// "2 · deu · Commentary", or just the number when the tags don't tell
+fn track_label(index: usize, tags: Option<&gst::TagList>) -> String {
+    let mut parts = vec![(index + 1).to_string()];
+    if let Some(tags) = tags {
+        let language = tags
+            .get::<gst::tags::LanguageName>()
+            .or_else(|| tags.get::<gst::tags::LanguageCode>());
+        parts.extend(language.map(|language| language.get().to_string()));
+        parts.extend(
+            tags.get::<gst::tags::Title>()
+                .map(|title| title.get().to_string()),
+        );
+    }
+    parts.join(" · ")
+}
@@ -0,0 +1,222 @@
+use std::sync::{Arc, Mutex};
+
+use gst::{prelude::*, Element};
+
+use crate::{
+    error::GstreamerError,
+    gstreamer::{add_ghost_pad, make_element, set_element_property, set_element_property_from_str},
+};
+
+const VIDEO_CONVERT: &str = "videoconvert";
+const VIDEO_FLIP: &str = "videoflip";
+const ASPECT_RATIO_CROP: &str = "aspectratiocrop";
+const VIDEO_SCALE: &str = "videoscale";
+const CAPS_FILTER: &str = "capsfilter";
+
+pub const ROTATION_NAMES: &[&str] = &["0°", "90°", "180°", "270°"];
+pub const ASPECT_RATIO_NAMES: &[&str] = &["Original", "4:3", "16:9", "2.35:1"];
+
+// Clockwise, on top of what the orientation tags already turned
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Rotation {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => Rotation::Quarter,
+            2 => Rotation::Half,
+            3 => Rotation::ThreeQuarters,
+            _ => Rotation::None,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Quarter => 1,
+            Rotation::Half => 2,
+            Rotation::ThreeQuarters => 3,
+        }
+    }
+
+    // videoflip's video-direction nick, mirroring after the rotation
+    fn video_direction(&self, mirrored: bool) -> &'static str {
+        match (self, mirrored) {
+            (Rotation::None, false) => "identity",
+            (Rotation::Quarter, false) => "90r",
+            (Rotation::Half, false) => "180",
+            (Rotation::ThreeQuarters, false) => "90l",
+            (Rotation::None, true) => "horiz",
+            (Rotation::Quarter, true) => "ul-lr",
+            (Rotation::Half, true) => "vert",
+            (Rotation::ThreeQuarters, true) => "ur-ll",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AspectRatio {
+    #[default]
+    Original,
+    Standard,
+    Wide,
+    Scope,
+}
+
+impl AspectRatio {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => AspectRatio::Standard,
+            2 => AspectRatio::Wide,
+            3 => AspectRatio::Scope,
+            _ => AspectRatio::Original,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        match self {
+            AspectRatio::Original => 0,
+            AspectRatio::Standard => 1,
+            AspectRatio::Wide => 2,
+            AspectRatio::Scope => 3,
+        }
+    }
+
+    fn fraction(&self) -> Option<gst::Fraction> {
+        match self {
+            AspectRatio::Original => None,
+            AspectRatio::Standard => Some(gst::Fraction::new(4, 3)),
+            AspectRatio::Wide => Some(gst::Fraction::new(16, 9)),
+            AspectRatio::Scope => Some(gst::Fraction::new(47, 20)),
+        }
+    }
+}
+
+// Orientation, crop and aspect ratio of the picture, between the filters and
+// the subtitles:
+//
+// videoconvert → videoflip (orientation tags) → videoflip (user) →
+// aspectratiocrop → videoscale → capsfilter
+//
+// Every stage passes frames through untouched while it isn't used.
+#[derive(Clone)]
+pub struct VideoTransform {
+    pub bin: gst::Bin,
+    user_flip: Element,
+    crop: Element,
+    aspect_filter: Element,
+    scale_sink_pad: gst::Pad,
+    // Display aspect ratio the picture is stretched to
+    forced_aspect: Arc<Mutex<Option<gst::Fraction>>>,
+}
+
+impl VideoTransform {
+    pub fn new() -> Result<Self, GstreamerError> {
+        let convert = make_element(VIDEO_CONVERT)?;
+        let tag_flip = make_element(VIDEO_FLIP)?;
+        set_element_property_from_str(&tag_flip, "video-direction", "auto")?;
+        let user_flip = make_element(VIDEO_FLIP)?;
+        let crop = make_element(ASPECT_RATIO_CROP)?;
+        let scale = make_element(VIDEO_SCALE)?;
+        let aspect_filter = make_element(CAPS_FILTER)?;
+
+        let bin = gst::Bin::with_name("video-transform-bin");
+        let elements = [
+            &convert,
+            &tag_flip,
+            &user_flip,
+            &crop,
+            &scale,
+            &aspect_filter,
+        ];
+        bin.add_many(elements)?;
+        Element::link_many(elements).map_err(|_| {
+            GstreamerError::Link(
+                "videoconvert → videoflip → videoflip → aspectratiocrop → videoscale → capsfilter"
+                    .to_string(),
+            )
+        })?;
+        add_ghost_pad(&bin, &convert, "sink")?;
+        add_ghost_pad(&bin, &aspect_filter, "src")?;
+
+        let scale_sink_pad = scale
+            .static_pad("sink")
+            .ok_or_else(|| glib::bool_error!("{VIDEO_SCALE} has no sink pad"))?;
+        let transform = Self {
+            bin,
+            user_flip,
+            crop,
+            aspect_filter,
+            scale_sink_pad,
+            forced_aspect: Arc::new(Mutex::new(None)),
+        };
+
+        // The stretched size depends on the incoming one, which changes with
+        // every file, rotation and crop
+        let transform_clone = transform.clone();
+        transform
+            .scale_sink_pad
+            .connect_notify(Some("caps"), move |_pad, _pspec| {
+                transform_clone.update_aspect_caps();
+            });
+        Ok(transform)
+    }
+
+    pub fn set_orientation(
+        &self,
+        rotation: Rotation,
+        mirrored: bool,
+    ) -> Result<(), GstreamerError> {
+        set_element_property_from_str(
+            &self.user_flip,
+            "video-direction",
+            rotation.video_direction(mirrored),
+        )
+    }
+
+    // Cuts the picture down to `aspect_ratio`, e.g. to drop letterbox bars
+    pub fn set_crop(&self, aspect_ratio: AspectRatio) -> Result<(), GstreamerError> {
+        let fraction = aspect_ratio
+            .fraction()
+            .unwrap_or_else(|| gst::Fraction::new(0, 1));
+        set_element_property(&self.crop, "aspect-ratio", fraction)
+    }
+
+    // Stretches the picture to `aspect_ratio`, keeping its height
+    pub fn set_aspect(&self, aspect_ratio: AspectRatio) {
+        *self.forced_aspect.lock().unwrap() = aspect_ratio.fraction();
+        self.update_aspect_caps();
+    }
+
+    fn update_aspect_caps(&self) {
+        let forced_aspect = *self.forced_aspect.lock().unwrap();
+        let info = self
+            .scale_sink_pad
+            .current_caps()
+            .and_then(|caps| gst_video::VideoInfo::from_caps(&caps).ok());
+
+        let caps = match (forced_aspect, info) {
+            (Some(aspect), Some(info)) => {
+                let height = info.height() as i32;
+                // Even widths keep chroma subsampled formats happy
+                let width = (height as i64 * aspect.numer() as i64 / aspect.denom() as i64) as i32;
+                gst_video::VideoCapsBuilder::new()
+                    .width(width + width % 2)
+                    .height(height)
+                    .pixel_aspect_ratio(gst::Fraction::new(1, 1))
+                    .build()
+            }
+            _ => gst::Caps::new_any(),
+        };
+        // Setting the same caps again would renegotiate for nothing
+        if self.aspect_filter.property::<gst::Caps>("caps") != caps {
+            self.aspect_filter.set_property("caps", &caps);
+        }
+    }
+}
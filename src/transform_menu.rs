@@ -0,0 +1,115 @@
+use std::rc::Rc;
+
+use gtk::{prelude::*, ApplicationWindow, Button};
+
+use crate::{
+    error_banner::ErrorBanner,
+    gstreamer::GstreamerManager,
+    transform::{AspectRatio, Rotation, ASPECT_RATIO_NAMES, ROTATION_NAMES},
+    window::add_action,
+};
+
+// Button with a popover correcting rotated phone footage and letterboxed
+// content; the orientation tags are always honoured before the rotation
+// picked here
+pub struct TransformMenu {
+    transform_button: gtk::MenuButton,
+}
+
+impl TransformMenu {
+    pub fn new(
+        window: &ApplicationWindow,
+        gst_manager: &GstreamerManager,
+        error_banner: ErrorBanner,
+    ) -> Self {
+        let transform_button = gtk::MenuButton::builder()
+            .label("⟳")
+            .tooltip_text("Rotate, mirror, crop and aspect ratio")
+            .valign(gtk::Align::Center)
+            .build();
+
+        let rotation_selector = gtk::DropDown::from_strings(ROTATION_NAMES);
+        let mirror_toggle = gtk::CheckButton::with_label("Mirror");
+        let crop_selector = gtk::DropDown::from_strings(ASPECT_RATIO_NAMES);
+        crop_selector.set_tooltip_text(Some("Crop to this aspect ratio, e.g. to drop black bars"));
+        let aspect_selector = gtk::DropDown::from_strings(ASPECT_RATIO_NAMES);
+        aspect_selector.set_tooltip_text(Some("Stretch to this aspect ratio"));
+        let reset_button = Button::with_label("Reset");
+
+        let transform_grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(10)
+            .build();
+        for (row, (title, widget)) in [
+            ("Rotate", rotation_selector.upcast_ref::<gtk::Widget>()),
+            ("", mirror_toggle.upcast_ref()),
+            ("Crop", crop_selector.upcast_ref()),
+            ("Aspect ratio", aspect_selector.upcast_ref()),
+            ("", reset_button.upcast_ref()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let title_label = gtk::Label::new(Some(title));
+            title_label.set_halign(gtk::Align::Start);
+            transform_grid.attach(&title_label, 0, row as i32, 1, 1);
+            transform_grid.attach(widget, 1, row as i32, 1, 1);
+        }
+        let popover = gtk::Popover::builder().child(&transform_grid).build();
+        transform_button.set_popover(Some(&popover));
+
+        let apply_orientation = {
+            let rotation_selector = rotation_selector.clone();
+            let mirror_toggle = mirror_toggle.clone();
+            let gst_manager = gst_manager.clone();
+            let error_banner = error_banner.clone();
+            move || {
+                let rotation = Rotation::from_index(rotation_selector.selected());
+                if let Err(err) = gst_manager.set_orientation(rotation, mirror_toggle.is_active()) {
+                    error_banner.show(&err);
+                }
+            }
+        };
+        let apply_orientation = Rc::new(apply_orientation);
+        let apply_orientation_clone = apply_orientation.clone();
+        rotation_selector.connect_selected_notify(move |_| apply_orientation_clone());
+        mirror_toggle.connect_toggled(move |_| apply_orientation());
+
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = error_banner.clone();
+        crop_selector.connect_selected_notify(move |crop_selector| {
+            let aspect_ratio = AspectRatio::from_index(crop_selector.selected());
+            if let Err(err) = gst_manager_clone.set_crop(aspect_ratio) {
+                error_banner_clone.show(&err);
+            }
+        });
+
+        let gst_manager_clone = gst_manager.clone();
+        aspect_selector.connect_selected_notify(move |aspect_selector| {
+            gst_manager_clone.set_aspect_ratio(AspectRatio::from_index(aspect_selector.selected()));
+        });
+
+        let rotation_selector_clone = rotation_selector.clone();
+        let mirror_toggle_clone = mirror_toggle.clone();
+        reset_button.connect_clicked(move |_| {
+            rotation_selector_clone.set_selected(Rotation::default().index());
+            mirror_toggle_clone.set_active(false);
+            crop_selector.set_selected(AspectRatio::default().index());
+            aspect_selector.set_selected(AspectRatio::default().index());
+        });
+
+        let rotation_count = ROTATION_NAMES.len() as u32;
+        add_action(window, "rotate", move || {
+            rotation_selector.set_selected((rotation_selector.selected() + 1) % rotation_count);
+        });
+        add_action(window, "mirror", move || {
+            mirror_toggle.set_active(!mirror_toggle.is_active());
+        });
+
+        Self { transform_button }
+    }
+
+    pub fn widget(&self) -> &gtk::MenuButton {
+        &self.transform_button
+    }
+}
@@ -0,0 +1,188 @@
+use std::{cell::RefCell, rc::Rc};
+
+use chrono::{DateTime, Utc};
+use gtk::{gio, prelude::*, ApplicationWindow, Button, Window};
+
+use crate::{
+    error::GstreamerError,
+    file_metadata::{FileMetadata, MediaInfo},
+    playlist::Playlist,
+    window::add_action,
+};
+
+// Button opening a window with the file details and what the Discoverer
+// found in the current item
+#[derive(Clone)]
+pub struct VideoInfo {
+    metadata_toggle: Button,
+    video_info_window: Rc<RefCell<Option<Window>>>,
+}
+
+impl VideoInfo {
+    pub fn new(main_window: &ApplicationWindow, playlist: Rc<RefCell<Playlist>>) -> Self {
+        let metadata_toggle = Button::with_label("Video Info");
+        metadata_toggle.set_valign(gtk::Align::Center);
+        let video_info_window: Rc<RefCell<Option<Window>>> = Rc::new(RefCell::new(None));
+
+        let main_window_clone = main_window.clone();
+        let video_info_window_clone = video_info_window.clone();
+
+        metadata_toggle.set_action_name(Some("win.video-info"));
+        add_action(main_window, "video-info", move || {
+            let mut video_info_window_borrow = video_info_window_clone.borrow_mut();
+            if video_info_window_borrow.is_none() {
+                let file_metadata =
+                    FileMetadata::new(playlist.borrow().current().unwrap_or_default());
+
+                let float_window = Window::builder()
+                    .title("Video Info")
+                    .default_width(414)
+                    .default_height(500)
+                    .transient_for(&main_window_clone)
+                    .modal(false)
+                    .resizable(false)
+                    .build();
+
+                let info_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+
+                let (file_path_title_text, file_path_text) = match &file_metadata.path {
+                    Some(path) => ("File path:", path.to_string_lossy().to_string()),
+                    None => ("URI:", file_metadata.uri.clone()),
+                };
+                info_box.append(&info_row(file_path_title_text, &file_path_text));
+
+                if let Some(metadata) = &file_metadata.metadata {
+                    let format_time = |time: std::time::SystemTime| {
+                        DateTime::<Utc>::from(time)
+                            .format("%Y-%m-%d %H:%M:%S")
+                            .to_string()
+                    };
+
+                    info_box.append(&info_row(
+                        "File size:",
+                        &format!("{} bytes", metadata.len()),
+                    ));
+                    if let Ok(created) = metadata.created() {
+                        info_box.append(&info_row("Created:", &format_time(created)));
+                    }
+                    if let Ok(modified) = metadata.modified() {
+                        info_box.append(&info_row("Modified:", &format_time(modified)));
+                    }
+                    if let Ok(accessed) = metadata.accessed() {
+                        info_box.append(&info_row("Accessed:", &format_time(accessed)));
+                    }
+
+                    let file_type = metadata.file_type();
+                    let file_type_label_text = if file_type.is_dir() {
+                        "Directory"
+                    } else if file_type.is_file() {
+                        "File"
+                    } else if file_type.is_symlink() {
+                        "Symbolic Link"
+                    } else {
+                        "Unknown"
+                    };
+                    info_box.append(&info_row("File type:", file_type_label_text));
+                }
+
+                // The Discoverer prerolls the whole file, which can take a
+                // while for large files and network streams
+                let media_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
+                let analysing_label = gtk::Label::new(Some("Analysing media…"));
+                media_box.append(&analysing_label);
+                info_box.append(&media_box);
+
+                let uri = file_metadata.uri.clone();
+                glib::spawn_future_local(async move {
+                    let result = gio::spawn_blocking(move || FileMetadata::discover(&uri))
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(GstreamerError::Discovery(
+                                "the analysis panicked".to_string(),
+                            ))
+                        });
+                    media_box.remove(&analysing_label);
+                    match result {
+                        Ok(media_info) => append_media_info(&media_box, &media_info),
+                        Err(err) => media_box.append(&gtk::Label::new(Some(&err.to_string()))),
+                    }
+                });
+
+                let info_scroll = gtk::ScrolledWindow::builder()
+                    .child(&info_box)
+                    .hscrollbar_policy(gtk::PolicyType::Never)
+                    .build();
+
+                float_window.set_child(Some(&info_scroll));
+                float_window.set_visible(true);
+                *video_info_window_borrow = Some(float_window);
+            } else if let Some(float_window) = &*video_info_window_borrow {
+                float_window.present();
+            }
+        });
+
+        Self {
+            metadata_toggle,
+            video_info_window,
+        }
+    }
+
+    pub fn widget(&self) -> &Button {
+        &self.metadata_toggle
+    }
+
+    // The window describes one item, so it goes when the playlist moves on
+    pub fn close(&self) {
+        if let Some(video_info_window) = self.video_info_window.borrow_mut().take() {
+            video_info_window.destroy();
+        }
+    }
+}
+
+// Title and value side by side, as in the video info window
+fn info_row(title: &str, value: &str) -> gtk::Box {
+    let title_label = gtk::Label::new(Some(title));
+    title_label.set_css_classes(&["bold-title"]);
+    title_label.set_halign(gtk::Align::Center);
+    let value_label = gtk::Label::new(Some(value));
+    value_label.set_halign(gtk::Align::Center);
+    value_label.set_wrap(true);
+    value_label.set_selectable(true);
+
+    let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    row_box.append(&title_label);
+    row_box.append(&value_label);
+    row_box
+}
+
+fn append_media_info(media_box: &gtk::Box, media_info: &MediaInfo) {
+    let section_title = |text: &str| {
+        let label = gtk::Label::new(Some(text));
+        label.set_css_classes(&["bold-title"]);
+        label.set_halign(gtk::Align::Start);
+        label.set_margin_top(10);
+        label
+    };
+
+    for (title, value) in media_info.rows() {
+        media_box.append(&info_row(&format!("{title}:"), &value));
+    }
+
+    for (index, stream) in media_info.streams.iter().enumerate() {
+        media_box.append(&section_title(&format!(
+            "Stream {} ({})",
+            index + 1,
+            stream.kind_name()
+        )));
+        for (title, value) in stream.rows().into_iter().chain(stream.tags.clone()) {
+            media_box.append(&info_row(&format!("{title}:"), &value));
+        }
+    }
+
+    if !media_info.tags.is_empty() {
+        media_box.append(&section_title("Tags"));
+        for (title, value) in &media_info.tags {
+            media_box.append(&info_row(&format!("{title}:"), value));
+        }
+    }
+}
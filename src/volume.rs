@@ -0,0 +1,105 @@
+use std::str::FromStr;
+
+use gstreamer_audio::{StreamVolume, StreamVolumeFormat};
+
+pub const VOLUME_SCALE_NAMES: &[&str] = &["Cubic", "Linear", "dB"];
+
+// Quietest level the dB slider goes down to, anything below is silence
+const MIN_DB: f64 = -60.0;
+const DB_STEP: f64 = 3.0;
+const STEP: f64 = 0.05;
+
+// How slider positions map to the volume the audio sink gets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VolumeScale {
+    #[default]
+    Cubic,
+    Linear,
+    Db,
+}
+
+impl VolumeScale {
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            1 => VolumeScale::Linear,
+            2 => VolumeScale::Db,
+            _ => VolumeScale::Cubic,
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        match self {
+            VolumeScale::Cubic => 0,
+            VolumeScale::Linear => 1,
+            VolumeScale::Db => 2,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VolumeScale::Cubic => "cubic",
+            VolumeScale::Linear => "linear",
+            VolumeScale::Db => "db",
+        }
+    }
+
+    fn format(&self) -> StreamVolumeFormat {
+        match self {
+            VolumeScale::Cubic => StreamVolumeFormat::Cubic,
+            VolumeScale::Linear => StreamVolumeFormat::Linear,
+            VolumeScale::Db => StreamVolumeFormat::Db,
+        }
+    }
+
+    pub fn range(&self) -> (f64, f64) {
+        match self {
+            VolumeScale::Cubic | VolumeScale::Linear => (0.0, 1.0),
+            VolumeScale::Db => (MIN_DB, 0.0),
+        }
+    }
+
+    pub fn step(&self) -> f64 {
+        match self {
+            VolumeScale::Cubic | VolumeScale::Linear => STEP,
+            VolumeScale::Db => DB_STEP,
+        }
+    }
+
+    pub fn slider_value(&self, linear: f64) -> f64 {
+        let (minimum, maximum) = self.range();
+        if linear <= 0.0 {
+            return minimum;
+        }
+        StreamVolume::convert_volume(StreamVolumeFormat::Linear, self.format(), linear)
+            .clamp(minimum, maximum)
+    }
+
+    pub fn linear_volume(&self, value: f64) -> f64 {
+        let (minimum, _) = self.range();
+        if value <= minimum {
+            return 0.0;
+        }
+        StreamVolume::convert_volume(self.format(), StreamVolumeFormat::Linear, value)
+    }
+
+    pub fn describe(&self, linear: f64) -> String {
+        match self {
+            VolumeScale::Db if linear <= 0.0 => "-∞ dB".to_string(),
+            VolumeScale::Db => format!("{:.1} dB", self.slider_value(linear)),
+            _ => format!("{:.0}%", self.slider_value(linear) * 100.0),
+        }
+    }
+}
+
+impl FromStr for VolumeScale {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "cubic" => Ok(VolumeScale::Cubic),
+            "linear" => Ok(VolumeScale::Linear),
+            "db" => Ok(VolumeScale::Db),
+            _ => Err(format!("Unknown volume scale '{name}'")),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{prelude::*, ApplicationWindow, Orientation};
+
+use crate::{
+    gstreamer::GstreamerManager,
+    settings::Settings,
+    volume::{VolumeScale, VOLUME_SCALE_NAMES},
+    window::add_action,
+};
+
+const VOLUME_ICON: &str = " ";
+const MUTED_ICON: &str = " ";
+
+// Mute toggle, volume slider and the scale the slider works in
+pub struct VolumeControls {
+    container: gtk::Box,
+}
+
+impl VolumeControls {
+    pub fn new(
+        window: &ApplicationWindow,
+        overlay: &gtk::Overlay,
+        settings: Rc<RefCell<Settings>>,
+        gst_manager: &GstreamerManager,
+    ) -> Self {
+        let mute_toggle = gtk::ToggleButton::with_label(VOLUME_ICON);
+        mute_toggle.set_valign(gtk::Align::Center);
+        let volume_slider = gtk::Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 0.05);
+        volume_slider.set_width_request(120);
+        volume_slider.set_valign(gtk::Align::Center);
+        let volume_label = gtk::Label::new(None);
+        volume_label.set_css_classes(&["time"]);
+        volume_label.set_width_chars(8);
+        volume_label.set_valign(gtk::Align::Center);
+        let volume_scale_selector = gtk::DropDown::from_strings(VOLUME_SCALE_NAMES);
+        volume_scale_selector.set_valign(gtk::Align::Center);
+
+        let container = gtk::Box::new(Orientation::Horizontal, 5);
+        container.append(&mute_toggle);
+        container.append(&volume_slider);
+        container.append(&volume_label);
+        container.append(&volume_scale_selector);
+
+        // The slider shows the volume in the selected scale, the settings
+        // and the pipeline always get it in linear scale
+        let (volume, muted, volume_scale) = {
+            let settings = settings.borrow();
+            (settings.volume, settings.muted, settings.volume_scale)
+        };
+        gst_manager.set_volume(volume);
+        gst_manager.set_muted(muted);
+        volume_label.set_text(&volume_scale.describe(volume));
+        mute_toggle.set_active(muted);
+        mute_toggle.set_label(if muted { MUTED_ICON } else { VOLUME_ICON });
+        set_volume_slider_scale(&volume_slider, volume_scale, volume);
+        volume_scale_selector.set_selected(volume_scale.index());
+
+        let settings_clone = settings.clone();
+        let gst_manager_clone = gst_manager.clone();
+        volume_slider.connect_value_changed(move |volume_slider| {
+            let mut settings = settings_clone.borrow_mut();
+            settings.volume = settings.volume_scale.linear_volume(volume_slider.value());
+            gst_manager_clone.set_volume(settings.volume);
+            volume_label.set_text(&settings.volume_scale.describe(settings.volume));
+        });
+
+        let settings_clone = settings.clone();
+        let gst_manager_clone = gst_manager.clone();
+        mute_toggle.connect_toggled(move |mute_toggle| {
+            let muted = mute_toggle.is_active();
+            settings_clone.borrow_mut().muted = muted;
+            gst_manager_clone.set_muted(muted);
+            mute_toggle.set_label(if muted { MUTED_ICON } else { VOLUME_ICON });
+        });
+
+        let volume_slider_clone = volume_slider.clone();
+        volume_scale_selector.connect_selected_notify(move |volume_scale_selector| {
+            let volume_scale = VolumeScale::from_index(volume_scale_selector.selected());
+            let volume = {
+                let mut settings = settings.borrow_mut();
+                settings.volume_scale = volume_scale;
+                settings.volume
+            };
+            set_volume_slider_scale(&volume_slider_clone, volume_scale, volume);
+        });
+
+        // Scrolling over the video changes the volume too
+        let scroll_controller =
+            gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+        let volume_slider_clone = volume_slider.clone();
+        scroll_controller.connect_scroll(move |_, _, dy| {
+            step_volume_slider(&volume_slider_clone, -dy);
+            glib::Propagation::Stop
+        });
+        overlay.add_controller(scroll_controller);
+
+        let volume_slider_clone = volume_slider.clone();
+        add_action(window, "volume-up", move || {
+            step_volume_slider(&volume_slider_clone, 1.0);
+        });
+        add_action(window, "volume-down", move || {
+            step_volume_slider(&volume_slider, -1.0);
+        });
+        add_action(window, "mute", move || {
+            mute_toggle.set_active(!mute_toggle.is_active());
+        });
+
+        Self { container }
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+}
+
+fn set_volume_slider_scale(volume_slider: &gtk::Scale, volume_scale: VolumeScale, volume: f64) {
+    let (minimum, maximum) = volume_scale.range();
+    let step = volume_scale.step();
+    volume_slider.set_range(minimum, maximum);
+    volume_slider.set_increments(step, step * 2.0);
+    volume_slider.set_value(volume_scale.slider_value(volume));
+}
+
+fn step_volume_slider(volume_slider: &gtk::Scale, steps: f64) {
+    let step = volume_slider.adjustment().step_increment();
+    volume_slider.set_value(volume_slider.value() + step * steps);
+}
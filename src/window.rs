@@ -1,28 +1,46 @@
 use std::{
     cell::{Cell, RefCell},
     rc::Rc,
-    time::Duration,
 };
 
-use chrono::{DateTime, Utc};
 use gst::prelude::ElementExt;
 use gst_video::prelude::*;
-use gtk::{prelude::*, Application, ApplicationWindow, Button, Orientation, Window};
+use gtk::{gio, prelude::*, Application, ApplicationWindow, Button, Orientation};
 
 use crate::{
-    file_metadata::FileMetadata,
-    filters::{FILTER_NAMES, NO_FILTER},
-    gstreamer::GstreamerManager,
+    bus::{EosAction, PlayerEvent, EOS_ACTION_NAMES},
+    capture_controls::CaptureControls,
+    error::GstreamerError,
+    error_banner::ErrorBanner,
+    filter_panel::FilterPanel,
+    fullscreen::FullscreenUi,
+    gstreamer::{Backend, GstreamerManager},
+    playlist::Playlist,
+    playlist_panel::PlaylistPanel,
+    seek_bar::SeekBar,
+    settings::Settings,
+    shortcuts::KeyBindings,
+    snapshot::SnapshotOptions,
+    speed::{index_for_speed, speed_from_index, NORMAL_SPEED_INDEX, PLAYBACK_SPEED_NAMES},
+    subtitle_menu::SubtitleMenu,
+    track_controls::TrackControls,
+    transform_menu::TransformMenu,
+    video_info::VideoInfo,
+    volume_controls::VolumeControls,
+    LOG_DOMAIN,
 };
 
 pub struct WindowManager {
     title: String,
     default_width: i32,
     default_height: i32,
-    video_path: String,
+    playlist: Rc<RefCell<Playlist>>,
     css_path: String,
-    is_dragging: Rc<Cell<bool>>,
-    video_info_window: Rc<RefCell<Option<gtk::Window>>>,
+    backend: Backend,
+    error_banner: ErrorBanner,
+    eos_action: Rc<Cell<EosAction>>,
+    snapshot_options: SnapshotOptions,
+    settings: Rc<RefCell<Settings>>,
 }
 
 impl WindowManager {
@@ -30,17 +48,28 @@ impl WindowManager {
         title: String,
         default_width: i32,
         default_height: i32,
-        video_path: String,
+        playlist: Playlist,
         css_path: String,
+        backend: Backend,
+        snapshot_options: SnapshotOptions,
     ) -> Self {
+        let error_banner = ErrorBanner::new();
+        let settings = Settings::load().unwrap_or_else(|err| {
+            error_banner.show(&err);
+            Settings::default()
+        });
+
         Self {
             title,
             default_width,
             default_height,
-            video_path,
+            playlist: Rc::new(RefCell::new(playlist)),
             css_path,
-            is_dragging: Rc::new(Cell::new(false)),
-            video_info_window: Rc::new(RefCell::new(None)),
+            backend,
+            error_banner,
+            eos_action: Rc::new(Cell::new(EosAction::default())),
+            snapshot_options,
+            settings: Rc::new(RefCell::new(settings)),
         }
     }
 
@@ -50,6 +79,7 @@ impl WindowManager {
         window.present();
     }
 
+    // Lays out the window; every feature builds and wires its own widgets
     fn build_window(&self, app: &Application) -> ApplicationWindow {
         let window = ApplicationWindow::builder()
             .application(app)
@@ -59,17 +89,7 @@ impl WindowManager {
             .build();
 
         let main_box = gtk::Box::new(Orientation::Vertical, 5);
-        let slider_box = gtk::Box::new(Orientation::Horizontal, 5);
-        let controls_box = gtk::Box::new(Orientation::Horizontal, 5);
-        let filter_selector_box = gtk::Box::new(Orientation::Horizontal, 5);
-
-        let filter_list = gtk::StringList::new(FILTER_NAMES);
-        let filter_selector = gtk::DropDown::builder().model(&filter_list).build();
-        filter_selector.set_hexpand(true);
-
-        filter_selector_box.append(&filter_selector);
-
-        main_box.append(&filter_selector_box);
+        main_box.append(self.error_banner.widget());
 
         let picture = gtk::Picture::new();
         picture.set_halign(gtk::Align::Center);
@@ -80,75 +100,160 @@ impl WindowManager {
         pause_image.set_pixel_size(64);
         pause_image.set_visible(false);
 
+        let buffering_bar = gtk::ProgressBar::new();
+        buffering_bar.set_valign(gtk::Align::End);
+        buffering_bar.set_show_text(true);
+        buffering_bar.set_visible(false);
+
         let overlay = gtk::Overlay::new();
         overlay.set_child(Some(&picture));
         overlay.add_overlay(&pause_image);
+        overlay.add_overlay(&buffering_bar);
+        overlay.set_hexpand(true);
+
+        let content_box = gtk::Box::new(Orientation::Horizontal, 5);
+        content_box.append(&overlay);
+        main_box.append(&content_box);
+        window.set_child(Some(&main_box));
 
-        main_box.append(&overlay);
+        // Keyboard shortcuts
+        self.load_shortcut_logic(&window);
 
+        let gst_manager = match self.load_gstreamer(picture) {
+            Ok(gst_manager) => gst_manager,
+            Err(err) => {
+                self.error_banner.show(&err);
+                return window;
+            }
+        };
+
+        // Filter stack
+        let filter_panel = FilterPanel::new(gst_manager.clone(), self.error_banner.clone());
+        content_box.prepend(filter_panel.widget());
+        self.load_filter_panel_actions(&window, filter_panel.clone());
+
+        // Playlist and the video info window, which follows the playlist
+        let video_info = VideoInfo::new(&window, self.playlist.clone());
+        let playlist_panel = PlaylistPanel::new(
+            &window,
+            self.playlist.clone(),
+            video_info.clone(),
+            &gst_manager,
+            self.error_banner.clone(),
+        );
+        content_box.append(playlist_panel.widget());
+
+        // Progress bar
+        let seek_bar = SeekBar::new(
+            &window,
+            self.settings.clone(),
+            &gst_manager,
+            self.error_banner.clone(),
+        );
+        main_box.append(seek_bar.widget());
+
+        // Playback row: transport, speed and volume
+        let previous_button = Button::with_label("⏮");
+        previous_button.set_action_name(Some("win.previous"));
         let play_button = Button::with_label("▶ Play");
-        play_button.set_valign(gtk::Align::Center);
         let pause_button = Button::with_label("⏸  Pause");
-        pause_button.set_valign(gtk::Align::Center);
         let stop_button = Button::with_label("⏹  Stop");
-        stop_button.set_valign(gtk::Align::Center);
-        let volume_toggle = Button::with_label(" ");
-        volume_toggle.set_valign(gtk::Align::Center);
-        let metadata_toggle = Button::with_label("Video Info");
-        metadata_toggle.set_valign(gtk::Align::Center);
-
-        controls_box.append(&play_button);
-        controls_box.append(&pause_button);
-        controls_box.append(&stop_button);
-        controls_box.append(&volume_toggle);
-        controls_box.append(&metadata_toggle);
-
-        let progress_slider = gtk::Scale::with_range(Orientation::Horizontal, 0.0, 100.0, 1.0);
-        progress_slider.set_hexpand(true);
-        progress_slider.set_valign(gtk::Align::Center);
-
-        let playtime_label = gtk::Label::new(None);
-        playtime_label.set_css_classes(&["time"]);
-        playtime_label.set_halign(gtk::Align::End);
-        playtime_label.set_valign(gtk::Align::Center);
-
-        slider_box.append(&playtime_label);
-        slider_box.append(&progress_slider);
-        main_box.append(&slider_box);
-
+        let next_button = Button::with_label("⏭");
+        next_button.set_action_name(Some("win.next"));
+        let speed_selector = gtk::DropDown::from_strings(PLAYBACK_SPEED_NAMES);
+        speed_selector.set_tooltip_text(Some("Playback speed"));
+        let volume_controls =
+            VolumeControls::new(&window, &overlay, self.settings.clone(), &gst_manager);
+
+        let playback_box = gtk::Box::new(Orientation::Horizontal, 5);
+        for widget in [
+            previous_button.upcast_ref::<gtk::Widget>(),
+            play_button.upcast_ref(),
+            pause_button.upcast_ref(),
+            stop_button.upcast_ref(),
+            next_button.upcast_ref(),
+            speed_selector.upcast_ref(),
+        ] {
+            widget.set_valign(gtk::Align::Center);
+            playback_box.append(widget);
+        }
+        playback_box.append(volume_controls.widget());
+
+        // Tools row: tracks, subtitles, picture, capture and info
+        let track_controls = TrackControls::new(&window, &gst_manager, self.error_banner.clone());
+        let subtitle_menu = SubtitleMenu::new(
+            &window,
+            self.settings.clone(),
+            self.playlist.clone(),
+            &gst_manager,
+            self.error_banner.clone(),
+        );
+        let transform_menu = TransformMenu::new(&window, &gst_manager, self.error_banner.clone());
+        let capture_controls = CaptureControls::new(
+            &window,
+            self.snapshot_options.clone(),
+            &gst_manager,
+            self.error_banner.clone(),
+        );
+        let eos_action_selector = gtk::DropDown::from_strings(EOS_ACTION_NAMES);
+        eos_action_selector.set_valign(gtk::Align::Center);
+        eos_action_selector.set_tooltip_text(Some("At the end of the playlist"));
+
+        let tools_box = gtk::Box::new(Orientation::Horizontal, 5);
+        tools_box.append(track_controls.widget());
+        tools_box.append(subtitle_menu.widget());
+        tools_box.append(transform_menu.widget());
+        tools_box.append(capture_controls.widget());
+        tools_box.append(&eos_action_selector);
+        tools_box.append(video_info.widget());
+
+        let controls_box = gtk::Box::new(Orientation::Vertical, 5);
         controls_box.set_halign(gtk::Align::Center);
+        playback_box.set_halign(gtk::Align::Center);
+        tools_box.set_halign(gtk::Align::Center);
+        controls_box.append(&playback_box);
+        controls_box.append(&tools_box);
         main_box.append(&controls_box);
 
-        window.set_child(Some(&main_box));
-
-        let gst_manager = self.load_gstreamer(picture);
+        // Keep the focus off the controls, so space and the arrows reach the
+        // shortcuts instead of the last clicked button
+        keep_focus_off_controls(seek_bar.widget());
+        keep_focus_off_controls(&controls_box);
 
-        // Slider
-        self.load_slider_movement(progress_slider.clone(), &gst_manager);
-        self.load_slider_interaction(progress_slider, &gst_manager);
-
-        // Playtime
-        self.load_playtime_indicator(playtime_label, &gst_manager);
+        // Frame stepping
+        self.load_frame_step_logic(&window, pause_image.clone(), &gst_manager);
 
         // Play button
-        self.load_play_button_logic(pause_image.clone(), play_button, &gst_manager);
+        self.load_play_button_logic(&window, pause_image.clone(), play_button, &gst_manager);
 
         // Pause button
-        self.load_pause_button_logic(pause_image.clone(), pause_button, &gst_manager);
+        self.load_pause_button_logic(&window, pause_image.clone(), pause_button, &gst_manager);
 
         // Stop button
-        self.load_stop_button_logic(stop_button, &gst_manager);
-
-        // Volume Toggle
-        self.load_volume_button_logic(volume_toggle, &gst_manager);
-
-        // Metedata Togle
-        self.load_video_info_button_logic(&window, metadata_toggle);
-
-        // Filter Selector
-        self.load_filter_selector_logic(
-            filter_selector,
-            Rc::new(RefCell::new(gst_manager.clone())),
+        self.load_stop_button_logic(&window, stop_button, &gst_manager);
+
+        // Playback speed
+        self.load_speed_logic(&window, speed_selector, &gst_manager);
+
+        // End of stream behaviour
+        self.load_eos_action_logic(eos_action_selector);
+
+        // Bus messages
+        self.load_bus_logic(pause_image, playlist_panel.clone(), &gst_manager);
+
+        // Network buffering
+        self.load_buffering_logic(buffering_bar, &gst_manager);
+
+        // Fullscreen
+        FullscreenUi::new(
+            &window,
+            main_box,
+            content_box,
+            overlay,
+            playlist_panel.widget().clone(),
+            seek_bar.widget().clone(),
+            controls_box,
+            filter_panel.widget().clone(),
         );
 
         // Close
@@ -167,319 +272,406 @@ impl WindowManager {
         );
     }
 
-    fn load_gstreamer(&self, picture: gtk::Picture) -> GstreamerManager {
-        let mut gst_manager = GstreamerManager::new();
-        gst_manager.create_pipeline(&self.video_path);
+    fn load_gstreamer(&self, picture: gtk::Picture) -> Result<GstreamerManager, GstreamerError> {
+        let mut gst_manager = GstreamerManager::new(self.backend)?;
+        let uri = self
+            .playlist
+            .borrow()
+            .current()
+            .unwrap_or_default()
+            .to_string();
+        gst_manager.create_pipeline(&uri)?;
 
-        // Print pipeline properties and elements
-        gst_manager.print_pipeline_properties();
-        gst_manager.list_elements();
+        glib::g_info!(LOG_DOMAIN, "Backend: {:?}", gst_manager.backend);
+        gst_manager.log_pipeline_properties();
+        gst_manager.log_elements();
 
         // BUG: do this to show video screen
-        gst_manager.pipeline.set_state(gst::State::Paused).unwrap();
-        gst_manager.pipeline.set_state(gst::State::Playing).unwrap();
+        for state in [gst::State::Paused, gst::State::Playing] {
+            gst_manager
+                .pipeline
+                .set_state(state)
+                .map_err(|_| GstreamerError::StateChange {
+                    element: gst_manager.pipeline.name().to_string(),
+                    state,
+                })?;
+        }
 
         let paintable = gst_manager.video_sink.property::<glib::Object>("paintable");
-        picture.set_paintable(Some(&paintable.downcast::<gtk::gdk::Paintable>().unwrap()));
-        gst_manager
+        picture.set_paintable(paintable.downcast_ref::<gtk::gdk::Paintable>());
+        Ok(gst_manager)
     }
 
-    fn load_slider_interaction(&self, progress_slider: gtk::Scale, gst_manager: &GstreamerManager) {
-        let pipeline_clone = gst_manager.pipeline.clone();
-        let is_dragging_clone = self.is_dragging.clone(); // Clone for the gesture handlers
-        let progress_slider_clone = progress_slider.clone(); // Clone for the drag-end closure
-
-        let gesture = gtk::GestureDrag::new();
-        progress_slider.add_controller(gesture.clone());
-
-        gesture.connect_drag_begin(move |_, _, _| {
-            is_dragging_clone.set(true);
+    fn load_frame_step_logic(
+        &self,
+        window: &ApplicationWindow,
+        pause_image: gtk::Image,
+        gst_manager: &GstreamerManager,
+    ) {
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = self.error_banner.clone();
+        let pause_image_clone = pause_image.clone();
+        add_action(window, "step-forward", move || {
+            pause_image_clone.set_visible(true);
+            if let Err(err) = gst_manager_clone.step_forward() {
+                error_banner_clone.show(&err);
+            }
         });
 
-        let pipeline_seek_clone = pipeline_clone.clone();
-        let is_dragging_end_clone = self.is_dragging.clone();
-        gesture.connect_drag_end(move |_, _, _| {
-            is_dragging_end_clone.set(false);
-
-            let seek_secs = progress_slider_clone.value();
-            let seek_ns = (seek_secs * 1_000_000_000.0) as u64;
-            let position = gst::ClockTime::from_nseconds(seek_ns);
-
-            pipeline_seek_clone
-                .seek_simple(gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT, position)
-                .expect("Failed to seek");
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = self.error_banner.clone();
+        add_action(window, "step-backward", move || {
+            pause_image.set_visible(true);
+            if let Err(err) = gst_manager_clone.step_backward() {
+                error_banner_clone.show(&err);
+            }
         });
     }
 
-    fn load_slider_movement(&self, progress_slider: gtk::Scale, gst_manager: &GstreamerManager) {
+    fn load_play_button_logic(
+        &self,
+        window: &ApplicationWindow,
+        pause_image: gtk::Image,
+        play_button: gtk::Button,
+        gst_manager: &GstreamerManager,
+    ) {
         let pipeline_clone = gst_manager.pipeline.clone();
-        let progress_slider_clone = progress_slider.clone();
+        let error_banner_clone = self.error_banner.clone();
 
-        glib::timeout_add_local(Duration::from_millis(50), move || {
-            if let (Some(position), Some(duration)) = (
-                pipeline_clone.query_position::<gst::ClockTime>(),
-                pipeline_clone.query_duration::<gst::ClockTime>(),
-            ) {
-                let pos_ns = position.nseconds();
-                let dur_ns = duration.nseconds();
-
-                let pos_secs = pos_ns as f64 / 1_000_000_000.0;
-                let dur_secs = dur_ns as f64 / 1_000_000_000.0;
-
-                progress_slider_clone.set_range(0.0, dur_secs);
-                progress_slider_clone.set_value(pos_secs);
+        add_action(window, "play", move || {
+            if set_pipeline_state(&pipeline_clone, gst::State::Playing, &error_banner_clone) {
+                pause_image.set_visible(false);
             }
+        });
+        play_button.set_action_name(Some("win.play"));
 
-            glib::ControlFlow::Continue
+        let pipeline_clone = gst_manager.pipeline.clone();
+        let window_clone = window.clone();
+        add_action(window, "play-pause", move || {
+            let action = if pipeline_clone.current_state() == gst::State::Playing {
+                "win.pause"
+            } else {
+                "win.play"
+            };
+            WidgetExt::activate_action(&window_clone, action, None).ok();
         });
     }
 
-    fn load_playtime_indicator(&self, playtime_label: gtk::Label, gst_manager: &GstreamerManager) {
+    fn load_pause_button_logic(
+        &self,
+        window: &ApplicationWindow,
+        pause_image: gtk::Image,
+        pause_button: gtk::Button,
+        gst_manager: &GstreamerManager,
+    ) {
         let pipeline_clone = gst_manager.pipeline.clone();
-        let playtime_label_clone = playtime_label.clone();
-
-        glib::timeout_add_local(Duration::from_millis(50), move || {
-            if let (Some(position), Some(duration)) = (
-                pipeline_clone.query_position::<gst::ClockTime>(),
-                pipeline_clone.query_duration::<gst::ClockTime>(),
-            ) {
-                let pos_secs = position.seconds();
-                let dur_secs = duration.seconds();
+        let error_banner_clone = self.error_banner.clone();
 
-                let format_time = |t: u64| format!("{:02}:{:02}", t / 60, t % 60);
-                let text = format!("{} / {}", format_time(pos_secs), format_time(dur_secs));
-
-                playtime_label_clone.set_text(&text);
+        add_action(window, "pause", move || {
+            if set_pipeline_state(&pipeline_clone, gst::State::Paused, &error_banner_clone) {
+                pause_image.set_visible(true);
             }
-
-            glib::ControlFlow::Continue
         });
+        pause_button.set_action_name(Some("win.pause"));
     }
 
-    fn load_play_button_logic(
+    fn load_stop_button_logic(
         &self,
-        pause_image: gtk::Image,
-        play_button: gtk::Button,
+        window: &ApplicationWindow,
+        stop_button: gtk::Button,
         gst_manager: &GstreamerManager,
     ) {
+        // TODO: Set the picture (video) to something default
         let pipeline_clone = gst_manager.pipeline.clone();
+        let error_banner_clone = self.error_banner.clone();
 
-        play_button.connect_clicked(move |_| {
-            pipeline_clone
-                .set_state(gst::State::Playing)
-                .expect("Failed play button");
-            pause_image.set_visible(false);
+        add_action(window, "stop", move || {
+            set_pipeline_state(&pipeline_clone, gst::State::Ready, &error_banner_clone);
         });
+        stop_button.set_action_name(Some("win.stop"));
     }
 
-    fn load_pause_button_logic(
+    fn load_speed_logic(
         &self,
-        pause_image: gtk::Image,
-        pause_button: gtk::Button,
+        window: &ApplicationWindow,
+        speed_selector: gtk::DropDown,
         gst_manager: &GstreamerManager,
     ) {
-        let pipeline_clone = gst_manager.pipeline.clone();
+        speed_selector.set_selected(NORMAL_SPEED_INDEX);
 
-        pause_button.connect_clicked(move |_| {
-            pipeline_clone
-                .set_state(gst::State::Paused)
-                .expect("Failed pause button");
-            pause_image.set_visible(true);
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = self.error_banner.clone();
+        speed_selector.connect_selected_notify(move |speed_selector| {
+            let rate = speed_from_index(speed_selector.selected());
+            if rate == gst_manager_clone.rate() {
+                return;
+            }
+            if let Err(err) = gst_manager_clone.set_rate(rate) {
+                error_banner_clone.show(&err);
+                speed_selector.set_selected(index_for_speed(gst_manager_clone.rate()));
+            }
         });
-    }
 
-    fn load_stop_button_logic(&self, stop_button: gtk::Button, gst_manager: &GstreamerManager) {
-        // TODO: Set the picture (video) to something default
-        let pipeline_clone = gst_manager.pipeline.clone();
+        // Every new stream starts at normal speed, so the rate is applied
+        // again once it has prerolled
+        let gst_manager_clone = gst_manager.clone();
+        let error_banner_clone = self.error_banner.clone();
+        gst_manager.bus.subscribe(move |event| {
+            if let PlayerEvent::StateChanged {
+                old: gst::State::Ready,
+                current: gst::State::Paused,
+            } = event
+            {
+                if let Err(err) = gst_manager_clone.apply_rate() {
+                    error_banner_clone.show(&err);
+                }
+            }
+        });
 
-        stop_button.connect_clicked(move |_| {
-            pipeline_clone
-                .set_state(gst::State::Ready)
-                .expect("Failed stop button");
+        let last_index = PLAYBACK_SPEED_NAMES.len() as u32 - 1;
+        let speed_selector_clone = speed_selector.clone();
+        add_action(window, "speed-down", move || {
+            let selected = speed_selector_clone.selected();
+            speed_selector_clone.set_selected(selected.saturating_sub(1));
+        });
+        let speed_selector_clone = speed_selector.clone();
+        add_action(window, "speed-up", move || {
+            let selected = speed_selector_clone.selected();
+            speed_selector_clone.set_selected((selected + 1).min(last_index));
+        });
+        add_action(window, "speed-reset", move || {
+            speed_selector.set_selected(NORMAL_SPEED_INDEX);
         });
     }
 
-    fn load_volume_button_logic(&self, volume_toggle: gtk::Button, gst_manager: &GstreamerManager) {
-        // TODO: Insted of toggle add a slider
-        let volume_element = gst_manager
-            .pipeline
-            .clone()
-            .by_name("volume0")
-            .expect("Volume element not found");
-
-        let is_muted = Rc::new(Cell::new(false));
-        let is_muted_clone = is_muted.clone();
-
-        volume_toggle.connect_clicked(move |volume_toggle| {
-            let current_mute = is_muted_clone.get();
-            let new_volume: f64 = if current_mute { 1.0 } else { 0.0 };
-            volume_element.set_property("volume", new_volume);
-            volume_toggle.set_label(if current_mute { " " } else { " " });
-            is_muted_clone.set(!current_mute);
+    fn load_eos_action_logic(&self, eos_action_selector: gtk::DropDown) {
+        let eos_action_clone = self.eos_action.clone();
+        eos_action_selector.connect_selected_notify(move |dropdown| {
+            eos_action_clone.set(EosAction::from_index(dropdown.selected()));
         });
     }
 
-    fn load_filter_selector_logic(
+    fn load_bus_logic(
         &self,
-        filter_selector: gtk::DropDown,
-        gst_manager: Rc<RefCell<GstreamerManager>>,
+        pause_image: gtk::Image,
+        playlist_panel: PlaylistPanel,
+        gst_manager: &GstreamerManager,
     ) {
         let gst_manager_clone = gst_manager.clone();
-        filter_selector.connect_selected_item_notify(move |dropdown| {
-            if let Some(item) = dropdown.selected_item() {
-                if let Some(text) = item
-                    .downcast_ref::<gtk::StringObject>()
-                    .map(|obj| obj.string().to_owned())
-                {
-                    if text.eq(NO_FILTER) {
-                        println!("filter: '{}'", text);
-                        gst_manager_clone
-                            .borrow_mut()
-                            .remove_filer_and_continue_pipeline();
-                    } else {
-                        println!("filter: '{}'", text);
-                        gst_manager_clone
-                            .borrow_mut()
-                            .set_filter_and_add_to_pipeline(&text);
+        let eos_action_clone = self.eos_action.clone();
+        let error_banner_clone = self.error_banner.clone();
+
+        gst_manager.bus.subscribe(move |event| match event {
+            // The only handler of the end of stream: the playlist moves on
+            // until it runs out of items, then the end of stream action applies
+            PlayerEvent::Eos => {
+                if playlist_panel.advance_on_finish() {
+                    return;
+                }
+
+                let pipeline = &gst_manager_clone.pipeline;
+                match eos_action_clone.get() {
+                    EosAction::Stop => {
+                        set_pipeline_state(pipeline, gst::State::Ready, &error_banner_clone);
+                    }
+                    EosAction::Rewind => {
+                        if set_pipeline_state(pipeline, gst::State::Paused, &error_banner_clone) {
+                            pause_image.set_visible(true);
+                        }
+                        if let Err(err) = gst_manager_clone.rewind() {
+                            error_banner_clone.show(&err);
+                        }
+                    }
+                    EosAction::Loop => {
+                        if let Err(err) = gst_manager_clone.rewind() {
+                            error_banner_clone.show(&err);
+                        }
                     }
                 }
             }
+            PlayerEvent::Error {
+                source,
+                message,
+                debug,
+            } => {
+                if let Some(debug) = debug {
+                    glib::g_debug!(LOG_DOMAIN, "Debug info from {source}: {debug}");
+                }
+                error_banner_clone.show(&format!("{source}: {message}"));
+            }
+            PlayerEvent::Warning {
+                source,
+                message,
+                debug,
+            } => {
+                glib::g_warning!(LOG_DOMAIN, "Warning from {source}: {message}");
+                if let Some(debug) = debug {
+                    glib::g_debug!(LOG_DOMAIN, "Debug info from {source}: {debug}");
+                }
+            }
+            PlayerEvent::Tags(tags) => {
+                glib::g_debug!(LOG_DOMAIN, "Tags: {tags}");
+            }
+            PlayerEvent::StateChanged { old, current } => {
+                glib::g_debug!(
+                    LOG_DOMAIN,
+                    "Pipeline state changed from {old:?} to {current:?}"
+                );
+            }
+            _ => {}
         });
     }
 
-    fn load_video_info_button_logic(
+    fn load_buffering_logic(
         &self,
-        main_window: &ApplicationWindow,
-        metadata_toggle: gtk::Button,
+        buffering_bar: gtk::ProgressBar,
+        gst_manager: &GstreamerManager,
     ) {
-        let main_window_clone = main_window.clone();
-        let video_info_window_clone = self.video_info_window.clone();
-        let file_metadata = FileMetadata::new(&self.video_path);
-
-        metadata_toggle.connect_clicked(move |_| {
-            let mut video_info_window_borrow = video_info_window_clone.borrow_mut();
-            if video_info_window_borrow.is_none() {
-                let float_window = Window::builder()
-                    .title("Video Info")
-                    .default_width(414)
-                    .default_height(300)
-                    .transient_for(&main_window_clone)
-                    .modal(false)
-                    .resizable(false)
-                    .build();
-
-                let info_box = gtk::Box::new(gtk::Orientation::Vertical, 5);
-                let file_path_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-
-                let file_path_title = gtk::Label::new(Some("File path:"));
-                file_path_title.set_css_classes(&["bold-title"]);
-                file_path_title.set_halign(gtk::Align::Center);
-                let file_path_label = gtk::Label::new(file_metadata.path.to_str());
-                file_path_label.set_halign(gtk::Align::Center);
-
-                file_path_box.append(&file_path_title);
-                file_path_box.append(&file_path_label);
-
-                info_box.append(&file_path_box);
-
-                // File size
-                let file_size_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-                let file_size_title = gtk::Label::new(Some("File size:"));
-                file_size_title.set_css_classes(&["bold-title"]);
-                file_size_title.set_halign(gtk::Align::Center);
-                let file_size_label =
-                    gtk::Label::new(Some(&format!("{} bytes", file_metadata.metadata.len())));
-                file_size_label.set_halign(gtk::Align::Center);
-                file_size_box.append(&file_size_title);
-                file_size_box.append(&file_size_label);
-                info_box.append(&file_size_box);
-
-                // Creation time
-                if let Ok(created) = file_metadata.metadata.created() {
-                    let creation_time_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-                    let creation_time_title = gtk::Label::new(Some("Created:"));
-                    creation_time_title.set_css_classes(&["bold-title"]);
-                    creation_time_title.set_halign(gtk::Align::Center);
-                    let creation_time_label = gtk::Label::new(Some(&format!(
-                        "{}",
-                        DateTime::<Utc>::from(created).format("%Y-%m-%d %H:%M:%S")
-                    )));
-                    creation_time_label.set_halign(gtk::Align::Center);
-                    creation_time_box.append(&creation_time_title);
-                    creation_time_box.append(&creation_time_label);
-                    info_box.append(&creation_time_box);
-                }
+        let gst_manager_clone = gst_manager.clone();
+        let resume_after_buffering = Rc::new(Cell::new(false));
+
+        gst_manager.bus.subscribe(move |event| {
+            let PlayerEvent::Buffering(percent) = event else {
+                return;
+            };
+
+            buffering_bar.set_visible(*percent < 100);
+            buffering_bar.set_fraction(f64::from(*percent) / 100.0);
+            buffering_bar.set_text(Some(&format!("Buffering {percent}%")));
 
-                // Last modification time
-                if let Ok(modified) = file_metadata.metadata.modified() {
-                    let modification_time_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-                    let modification_time_title = gtk::Label::new(Some("Modified:"));
-                    modification_time_title.set_css_classes(&["bold-title"]);
-                    modification_time_title.set_halign(gtk::Align::Center);
-                    let modification_time_label = gtk::Label::new(Some(&format!(
-                        "{}",
-                        DateTime::<Utc>::from(modified).format("%Y-%m-%d %H:%M:%S")
-                    )));
-                    modification_time_label.set_halign(gtk::Align::Center);
-                    modification_time_box.append(&modification_time_title);
-                    modification_time_box.append(&modification_time_label);
-                    info_box.append(&modification_time_box);
+            // Live sources can't be paused while they fill up
+            if gst_manager_clone.is_live() {
+                return;
+            }
+
+            let pipeline = &gst_manager_clone.pipeline;
+            if *percent < 100 {
+                if pipeline.current_state() == gst::State::Playing && !resume_after_buffering.get()
+                {
+                    pipeline.set_state(gst::State::Paused).ok();
+                    resume_after_buffering.set(true);
                 }
+            } else if resume_after_buffering.get() {
+                resume_after_buffering.set(false);
+                pipeline.set_state(gst::State::Playing).ok();
+            }
+        });
+    }
 
-                // Last access time
-                if let Ok(accessed) = file_metadata.metadata.accessed() {
-                    let access_time_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-                    let access_time_title = gtk::Label::new(Some("Accessed:"));
-                    access_time_title.set_css_classes(&["bold-title"]);
-                    access_time_title.set_halign(gtk::Align::Center);
-                    let access_time_label = gtk::Label::new(Some(&format!(
-                        "{}",
-                        DateTime::<Utc>::from(accessed).format("%Y-%m-%d %H:%M:%S")
-                    )));
-                    access_time_label.set_halign(gtk::Align::Center);
-                    access_time_box.append(&access_time_title);
-                    access_time_box.append(&access_time_label);
-                    info_box.append(&access_time_box);
+    fn load_shortcut_logic(&self, window: &ApplicationWindow) {
+        let key_bindings = Rc::new(RefCell::new(KeyBindings::new(
+            &self.settings.borrow().shortcuts,
+        )));
+        if !key_bindings.borrow().problems().is_empty() {
+            self.error_banner.show(&format!(
+                "Some keyboard shortcuts were left out: {}",
+                key_bindings.borrow().problems().join("; ")
+            ));
+        }
+        let controller = Rc::new(RefCell::new(key_bindings.borrow().controller()));
+        window.add_controller(controller.borrow().clone());
+
+        let window_clone = window.clone();
+        let settings_clone = self.settings.clone();
+        add_action(window, "shortcuts", move || {
+            let key_bindings_clone = key_bindings.clone();
+            let controller_clone = controller.clone();
+            let window = window_clone.clone();
+            let settings_clone = settings_clone.clone();
+            let remap = move |action: &str, accel: String| {
+                let mut overrides = settings_clone.borrow().shortcuts.clone();
+                overrides.insert(action.to_string(), vec![accel]);
+                let remapped = KeyBindings::new(&overrides);
+                // Only refuse what this change broke, not what was broken before
+                let known = key_bindings_clone.borrow().problems().to_vec();
+                if let Some(problem) = remapped
+                    .problems()
+                    .iter()
+                    .find(|problem| !known.contains(problem))
+                {
+                    return Err(problem.clone());
                 }
 
-                // File type
-                let file_type_box = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-                let file_type_title = gtk::Label::new(Some("File type:"));
-                file_type_title.set_css_classes(&["bold-title"]);
-                file_type_title.set_halign(gtk::Align::Center);
-                let file_type = file_metadata.metadata.file_type();
-                let file_type_label_text = if file_type.is_dir() {
-                    "Directory"
-                } else if file_type.is_file() {
-                    "File"
-                } else if file_type.is_symlink() {
-                    "Symbolic Link"
-                } else {
-                    "Unknown"
-                };
-                let file_type_label = gtk::Label::new(Some(file_type_label_text));
-                file_type_label.set_halign(gtk::Align::Center);
-                file_type_box.append(&file_type_title);
-                file_type_box.append(&file_type_label);
-                info_box.append(&file_type_box);
-
-                // TODO: Add more metadata properties
-
-                float_window.set_child(Some(&info_box));
-                float_window.set_visible(true);
-                *video_info_window_borrow = Some(float_window);
-            } else if let Some(float_window) = &*video_info_window_borrow {
-                float_window.present();
-            }
+                settings_clone.borrow_mut().shortcuts = overrides;
+                settings_clone
+                    .borrow()
+                    .save()
+                    .map_err(|err| err.to_string())?;
+                let new_controller = remapped.controller();
+                window.remove_controller(&controller_clone.replace(new_controller.clone()));
+                window.add_controller(new_controller);
+                key_bindings_clone.replace(remapped);
+                Ok(())
+            };
+            key_bindings
+                .borrow()
+                .help_window(&window_clone, remap)
+                .present();
+        });
+
+        let window_clone = window.clone();
+        add_action(window, "quit", move || window_clone.close());
+    }
+
+    fn load_filter_panel_actions(&self, window: &ApplicationWindow, filter_panel: FilterPanel) {
+        let filter_panel_clone = filter_panel.clone();
+        add_action(window, "filter-panel", move || {
+            let panel = filter_panel_clone.widget();
+            panel.set_visible(!panel.is_visible());
+        });
+
+        add_action(window, "clear-filters", move || {
+            filter_panel.clear_filters()
         });
     }
 
     fn load_close_logic(&self, window: &ApplicationWindow, gst_manager: &GstreamerManager) {
         let pipeline_clone = gst_manager.pipeline.clone();
+        let settings_clone = self.settings.clone();
 
         window.connect_close_request(move |_| {
             pipeline_clone.set_state(gst::State::Null).ok();
+            if let Err(err) = settings_clone.borrow().save() {
+                glib::g_warning!(LOG_DOMAIN, "Failed to save settings: {err}");
+            }
             glib::Propagation::Proceed
         });
     }
 }
+
+// Registers `win.<name>` for buttons and the keyboard shortcuts. Actions live
+// on the window, so every window drives its own player.
+pub fn add_action(window: &ApplicationWindow, name: &str, activate: impl Fn() + 'static) {
+    let action = gio::SimpleAction::new(name, None);
+    action.connect_activate(move |_, _| activate());
+    window.add_action(&action);
+}
+
+// Also covers the groups of controls, but not the popovers of menu buttons
+fn keep_focus_off_controls(controls: &gtk::Box) {
+    let mut child = controls.first_child();
+    while let Some(widget) = child {
+        widget.set_focus_on_click(false);
+        if let Some(group) = widget.downcast_ref::<gtk::Box>() {
+            keep_focus_off_controls(group);
+        }
+        child = widget.next_sibling();
+    }
+}
+
+pub fn set_pipeline_state(
+    pipeline: &gst::Pipeline,
+    state: gst::State,
+    error_banner: &ErrorBanner,
+) -> bool {
+    match pipeline.set_state(state) {
+        Ok(_) => true,
+        Err(_) => {
+            error_banner.show(&GstreamerError::StateChange {
+                element: pipeline.name().to_string(),
+                state,
+            });
+            false
+        }
+    }
+}